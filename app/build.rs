@@ -0,0 +1,37 @@
+// 以 c_abi 特性构建时检查已提交的 C 头文件与 pkg-config 模板是否与 c_api.rs 一致,
+// 只读不写,过期时构建失败
+#[cfg(feature = "c_abi")]
+#[path = "build/header.rs"]
+mod header;
+
+fn main() {
+    println!("cargo:rerun-if-changed=build.rs");
+
+    #[cfg(feature = "c_abi")]
+    {
+        use std::path::Path;
+
+        for path in [
+            header::C_API_PATH,
+            header::ERROR_PATH,
+            header::CARGO_TOML_PATH,
+            header::HEADER_PATH,
+            header::PKG_CONFIG_PATH,
+            "build/header.rs",
+        ] {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+        let stale = match header::write_or_check(dir, true) {
+            Ok(stale) => stale,
+            Err(e) => panic!("生成 C 头文件失败: {}", e),
+        };
+        if !stale.is_empty() {
+            let files: Vec<String> = stale.iter().map(|p| p.display().to_string()).collect();
+            panic!(
+                "{} 与 c_api.rs 不一致,请运行 cargo run -p script -- header",
+                files.join(", ")
+            );
+        }
+    }
+}
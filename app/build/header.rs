@@ -0,0 +1,345 @@
+// 由 app/build.rs 与 script 共用,路径均相对于 app 目录
+use quote::ToTokens;
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+use syn::{Attribute, Fields, FnArg, Item, Pat, ReturnType, Type};
+
+pub const HEADER_PATH: &str = "include/phi_save_codec.h";
+pub const PKG_CONFIG_PATH: &str = "phi_save_codec.pc.in";
+
+pub const C_API_PATH: &str = "src/c_api.rs";
+pub const ERROR_PATH: &str = "src/error.rs";
+pub const CARGO_TOML_PATH: &str = "Cargo.toml";
+
+// impl_c_api 系列宏的前两个参数是类型,其余都是导出函数名;export_c_api 只有第一个参数是函数名
+pub fn macro_export_names(mac: &syn::Macro) -> Option<Vec<String>> {
+    let (skip, take) = if mac.path.is_ident("export_c_api") {
+        (0, 1)
+    } else if ["impl_c_api", "impl_c_api_encrypted", "impl_c_api_diff"]
+        .iter()
+        .any(|name| mac.path.is_ident(name))
+    {
+        (2, usize::MAX)
+    } else {
+        return None;
+    };
+
+    let tokens_str = mac.tokens.to_string();
+    let params_str = tokens_str.trim_start_matches('(').trim_end_matches(')');
+    Some(
+        params_str
+            .split(',')
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .skip(skip)
+            .take(take)
+            .collect(),
+    )
+}
+
+struct CFunction {
+    name: String,
+    ret: String,
+    params: Vec<(String, String)>,
+    feature: Option<String>,
+}
+
+// 只处理 c_api.rs 中出现过的类型,遇到其他类型直接报错,避免生成错误的声明
+fn c_type(ty: &Type) -> Result<String, String> {
+    match ty {
+        Type::Ptr(p) => {
+            let inner = c_type(&p.elem)?;
+            if p.const_token.is_some() {
+                Ok(format!("const {} *", inner))
+            } else {
+                Ok(format!("{} *", inner))
+            }
+        }
+        Type::Path(p) => {
+            let ident = p.path.segments.last().map(|s| s.ident.to_string());
+            match ident.as_deref() {
+                Some("u8") => Ok("uint8_t".into()),
+                Some("u16") => Ok("uint16_t".into()),
+                Some("u32") => Ok("uint32_t".into()),
+                Some("i32") => Ok("int32_t".into()),
+                Some("usize") => Ok("size_t".into()),
+                Some("bool") => Ok("bool".into()),
+                Some("Data") => Ok("Data".into()),
+                _ => Err(format!("不支持的类型: {}", quote_type(ty))),
+            }
+        }
+        Type::Tuple(t) if t.elems.is_empty() => Ok("void".into()),
+        _ => Err(format!("不支持的类型: {}", quote_type(ty))),
+    }
+}
+
+fn quote_type(ty: &Type) -> String {
+    ty.to_token_stream().to_string()
+}
+
+fn declare(ty: &str, name: &str) -> String {
+    if ty.ends_with('*') {
+        format!("{}{}", ty, name)
+    } else {
+        format!("{} {}", ty, name)
+    }
+}
+
+fn cfg_feature(attrs: &[Attribute]) -> Option<String> {
+    attrs.iter().find_map(|attr| {
+        if !attr.path().is_ident("cfg") {
+            return None;
+        }
+        let mut feature = None;
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("feature") {
+                let value: syn::LitStr = meta.value()?.parse()?;
+                feature = Some(value.value());
+            }
+            Ok(())
+        });
+        feature
+    })
+}
+
+fn is_export(f: &syn::ItemFn) -> bool {
+    let no_mangle = f
+        .attrs
+        .iter()
+        .any(|a| a.to_token_stream().to_string().contains("no_mangle"));
+    let extern_c = f
+        .sig
+        .abi
+        .as_ref()
+        .and_then(|abi| abi.name.as_ref())
+        .is_some_and(|n| n.value() == "C");
+    no_mangle && extern_c
+}
+
+fn collect(file: &syn::File) -> Result<(Vec<String>, Vec<CFunction>), String> {
+    let mut structs = Vec::new();
+    let mut funcs = Vec::new();
+    let mut ex = Vec::new();
+
+    for item in &file.items {
+        match item {
+            Item::Struct(s) if s.attrs.iter().any(|a| a.path().is_ident("repr")) => {
+                let Fields::Named(fields) = &s.fields else {
+                    continue;
+                };
+                let mut out = format!("typedef struct {} {{\n", s.ident);
+                for field in &fields.named {
+                    let name = field
+                        .ident
+                        .as_ref()
+                        .map(|i| i.to_string())
+                        .unwrap_or_default();
+                    let _ = writeln!(out, "    {};", declare(&c_type(&field.ty)?, &name));
+                }
+                let _ = write!(out, "}} {};", s.ident);
+                structs.push(out);
+            }
+            // 宏生成的导出函数签名都是 (const uint8_t *data_ptr, size_t data_len) -> Data,
+            // 同时生成带 _ex 后缀、返回错误码的第二代版本
+            Item::Macro(m) => {
+                let Some(names) = macro_export_names(&m.mac) else {
+                    continue;
+                };
+                let input = vec![
+                    ("const uint8_t *".to_string(), "data_ptr".to_string()),
+                    ("size_t".to_string(), "data_len".to_string()),
+                ];
+                let feature = cfg_feature(&m.attrs);
+                for name in names {
+                    let mut ex_params = input.clone();
+                    ex_params.push(("Data *".into(), "out".into()));
+                    ex_params.push(("Data *".into(), "err_out".into()));
+                    ex.push(CFunction {
+                        name: format!("{}_ex", name),
+                        ret: "int32_t".into(),
+                        params: ex_params,
+                        feature: feature.clone(),
+                    });
+                    funcs.push(CFunction {
+                        name,
+                        ret: "Data".into(),
+                        params: input.clone(),
+                        feature: feature.clone(),
+                    });
+                }
+            }
+            Item::Fn(f) if is_export(f) => {
+                let ret = match &f.sig.output {
+                    ReturnType::Default => "void".to_string(),
+                    ReturnType::Type(_, ty) => c_type(ty)?,
+                };
+                let mut params = Vec::new();
+                for input in &f.sig.inputs {
+                    let FnArg::Typed(arg) = input else {
+                        continue;
+                    };
+                    let name = match arg.pat.as_ref() {
+                        Pat::Ident(i) => i.ident.to_string(),
+                        _ => "arg".to_string(),
+                    };
+                    params.push((c_type(&arg.ty)?, name));
+                }
+                funcs.push(CFunction {
+                    name: f.sig.ident.to_string(),
+                    ret,
+                    params,
+                    feature: cfg_feature(&f.attrs),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    funcs.extend(ex);
+    Ok((structs, funcs))
+}
+
+fn screaming_snake(name: &str) -> String {
+    let mut out = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.push(c.to_ascii_uppercase());
+    }
+    out
+}
+
+fn error_codes(file: &syn::File) -> Vec<(String, String)> {
+    file.items
+        .iter()
+        .find_map(|item| match item {
+            Item::Enum(e) if e.ident == "ErrorCode" => Some(e),
+            _ => None,
+        })
+        .map(|e| {
+            e.variants
+                .iter()
+                .map(|v| {
+                    let value = v
+                        .discriminant
+                        .as_ref()
+                        .map(|(_, expr)| expr.to_token_stream().to_string())
+                        .unwrap_or_default();
+                    (
+                        format!("PSC_{}", screaming_snake(&v.ident.to_string())),
+                        value,
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+pub fn generate_header(dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let c_api = syn::parse_file(&fs::read_to_string(dir.join(C_API_PATH))?)?;
+    let error = syn::parse_file(&fs::read_to_string(dir.join(ERROR_PATH))?)?;
+    let (structs, funcs) = collect(&c_api)?;
+
+    let abi_version = c_api.items.iter().find_map(|item| match item {
+        Item::Const(c) if c.ident == "ABI_VERSION" => Some(c.expr.to_token_stream().to_string()),
+        _ => None,
+    });
+
+    let mut out = String::new();
+    out.push_str(
+        "/* 由 cargo run -p script -- header 根据 app/src/c_api.rs 生成,请勿手动修改 */\n",
+    );
+    out.push_str("#ifndef PHI_SAVE_CODEC_H\n#define PHI_SAVE_CODEC_H\n\n");
+    out.push_str("#include <stdbool.h>\n#include <stddef.h>\n#include <stdint.h>\n\n");
+    out.push_str("#ifdef __cplusplus\nextern \"C\" {\n#endif\n\n");
+    if let Some(v) = abi_version {
+        let _ = writeln!(out, "#define PSC_ABI_VERSION {}\n", v);
+    }
+
+    out.push_str("typedef enum PscErrorCode {\n");
+    for (name, value) in error_codes(&error) {
+        let _ = writeln!(out, "    {} = {},", name, value);
+    }
+    out.push_str("} PscErrorCode;\n\n");
+
+    for s in structs {
+        let _ = writeln!(out, "{}\n", s);
+    }
+
+    for f in funcs {
+        if let Some(feature) = &f.feature {
+            let _ = writeln!(out, "/* 需要启用 {} 特性 */", feature);
+        }
+        let params = if f.params.is_empty() {
+            "void".to_string()
+        } else {
+            f.params
+                .iter()
+                .map(|(ty, name)| declare(ty, name))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let _ = writeln!(
+            out,
+            "{};",
+            declare(&f.ret, &format!("{}({})", f.name, params))
+        );
+    }
+
+    out.push_str("\n#ifdef __cplusplus\n}\n#endif\n\n#endif /* PHI_SAVE_CODEC_H */\n");
+    Ok(out)
+}
+
+fn manifest_value(manifest: &str, key: &str) -> String {
+    manifest
+        .lines()
+        .find_map(|line| {
+            let (k, v) = line.split_once('=')?;
+            (k.trim() == key).then(|| v.trim().trim_matches('"').to_string())
+        })
+        .unwrap_or_default()
+}
+
+// prefix 由安装步骤替换 @PREFIX@ 得到
+pub fn generate_pkg_config(dir: &Path) -> Result<String, Box<dyn std::error::Error>> {
+    let manifest = fs::read_to_string(dir.join(CARGO_TOML_PATH))?;
+    Ok(format!(
+        "prefix=@PREFIX@\n\
+         exec_prefix=${{prefix}}\n\
+         libdir=${{exec_prefix}}/lib\n\
+         includedir=${{prefix}}/include\n\
+         \n\
+         Name: {name}\n\
+         Description: {description}\n\
+         Version: {version}\n\
+         Libs: -L${{libdir}} -l{name}\n\
+         Cflags: -I${{includedir}}\n",
+        name = manifest_value(&manifest, "name"),
+        description = manifest_value(&manifest, "description"),
+        version = manifest_value(&manifest, "version"),
+    ))
+}
+
+// 返回内容不一致的文件;check 为 true 时只比较不写入,内容一致的文件不会被改写
+pub fn write_or_check(dir: &Path, check: bool) -> Result<Vec<PathBuf>, Box<dyn std::error::Error>> {
+    let mut stale = Vec::new();
+    for (path, content) in [
+        (HEADER_PATH, generate_header(dir)?),
+        (PKG_CONFIG_PATH, generate_pkg_config(dir)?),
+    ] {
+        let path = dir.join(path);
+        if fs::read_to_string(&path).ok().as_deref() == Some(content.as_str()) {
+            continue;
+        }
+        if !check {
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)?;
+        }
+        stale.push(path);
+    }
+    Ok(stale)
+}
@@ -0,0 +1,154 @@
+/* 由 cargo run -p script -- header 根据 app/src/c_api.rs 生成,请勿手动修改 */
+#ifndef PHI_SAVE_CODEC_H
+#define PHI_SAVE_CODEC_H
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define PSC_ABI_VERSION 2
+
+typedef enum PscErrorCode {
+    PSC_OK = 0,
+    PSC_INVALID_INPUT = 1,
+    PSC_TRUNCATED = 2,
+    PSC_INVALID_STRING = 3,
+    PSC_VAR_INT_OUT_OF_RANGE = 4,
+    PSC_UNSUPPORTED_VERSION = 5,
+    PSC_SERIALIZATION = 6,
+    PSC_ENCRYPTION = 7,
+    PSC_TRAILING_DATA = 8,
+    PSC_NON_CANONICAL_VAR_INT = 9,
+    PSC_INVALID_VALUE = 10,
+} PscErrorCode;
+
+typedef struct Data {
+    size_t len;
+    uint8_t *ptr;
+} Data;
+
+uint8_t *psc_malloc(size_t len);
+bool psc_free(uint8_t *ptr, size_t len);
+bool psc_data_free(Data data);
+Data psc_get_last_error(void);
+int32_t psc_get_last_error_code(void);
+bool psc_clear_last_error(void);
+Data psc_parse_user(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_user(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_user_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_user_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_summary(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_summary(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_summary_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_summary_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_game_record(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_game_record(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_game_record_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_game_record_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_game_progress(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_game_progress(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_game_progress_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_game_progress_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_game_key(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_game_key(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_game_key_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_game_key_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_settings(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_settings(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_settings_json(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_settings_json(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_decrypt_user(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_encrypt_user(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_decrypt_game_record(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_encrypt_game_record(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_decrypt_game_progress(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_encrypt_game_progress(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_decrypt_game_key(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_encrypt_game_key(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_decrypt_settings(const uint8_t *data_ptr, size_t data_len);
+/* 需要启用 encryption 特性 */
+Data psc_encrypt_settings(const uint8_t *data_ptr, size_t data_len);
+Data psc_diff_user(const uint8_t *data_ptr, size_t data_len);
+Data psc_diff_summary(const uint8_t *data_ptr, size_t data_len);
+Data psc_diff_game_record(const uint8_t *data_ptr, size_t data_len);
+Data psc_diff_game_progress(const uint8_t *data_ptr, size_t data_len);
+Data psc_diff_game_key(const uint8_t *data_ptr, size_t data_len);
+Data psc_diff_settings(const uint8_t *data_ptr, size_t data_len);
+Data psc_best_n(const uint8_t *data_ptr, size_t data_len);
+Data psc_parse_summary_b64(const uint8_t *data_ptr, size_t data_len);
+Data psc_build_summary_b64(const uint8_t *data_ptr, size_t data_len);
+uint32_t psc_abi_version(void);
+Data psc_crate_version(void);
+Data psc_capabilities(void);
+int32_t psc_parse_user_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_user_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_user_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_user_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_summary_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_summary_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_summary_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_summary_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_game_record_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_game_record_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_game_record_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_game_record_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_game_progress_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_game_progress_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_game_progress_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_game_progress_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_game_key_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_game_key_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_game_key_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_game_key_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_settings_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_settings_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_settings_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_settings_json_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_decrypt_user_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_encrypt_user_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_decrypt_game_record_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_encrypt_game_record_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_decrypt_game_progress_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_encrypt_game_progress_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_decrypt_game_key_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_encrypt_game_key_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_decrypt_settings_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+/* 需要启用 encryption 特性 */
+int32_t psc_encrypt_settings_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_diff_user_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_diff_summary_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_diff_game_record_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_diff_game_progress_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_diff_game_key_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_diff_settings_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_best_n_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_parse_summary_b64_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+int32_t psc_build_summary_b64_ex(const uint8_t *data_ptr, size_t data_len, Data *out, Data *err_out);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PHI_SAVE_CODEC_H */
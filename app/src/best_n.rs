@@ -0,0 +1,113 @@
+use crate::game_record::field::GameRecord;
+use crate::game_record::song::Difficulty;
+use crate::rks::{BEST_PHI, ChartConstants, LevelRks, level_rks_list, player_rks_n};
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+pub const B19: usize = 19;
+pub const B27: usize = 27;
+pub const B30: usize = 30;
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BestEntry {
+    pub song_id: String,
+    pub difficulty: Difficulty,
+    pub score: u32,
+    pub acc: f32,
+    pub fc: bool,
+    pub constant: f32,
+    pub rks: f32,
+}
+
+// rks 由 best 与 phi 两个列表算出: (Σbest + Σphi) / (n + 3),n 为 27 时与游戏一致
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct BestReport {
+    pub rks: f32,
+    pub best: Vec<BestEntry>,
+    pub phi: Vec<BestEntry>,
+}
+
+impl From<&LevelRks> for BestEntry {
+    fn from(l: &LevelRks) -> Self {
+        BestEntry {
+            song_id: l.song_id.clone(),
+            difficulty: l.difficulty,
+            score: l.score,
+            acc: l.acc,
+            fc: l.fc,
+            constant: l.constant,
+            rks: l.rks,
+        }
+    }
+}
+
+pub fn best_n<C: ChartConstants + ?Sized>(
+    record: &GameRecord,
+    constants: &C,
+    n: usize,
+) -> BestReport {
+    let sorted = level_rks_list(record, constants);
+
+    BestReport {
+        rks: player_rks_n(&sorted, n),
+        best: sorted.iter().take(n).map(BestEntry::from).collect(),
+        phi: sorted
+            .iter()
+            .filter(|l| l.is_phi())
+            .take(BEST_PHI)
+            .map(BestEntry::from)
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart_table::ChartTable;
+    use crate::game_record::song::ChartRecord;
+
+    fn record() -> (GameRecord, ChartTable) {
+        let mut record = GameRecord::default();
+        let mut table = ChartTable::new();
+        for i in 0..40 {
+            let song = format!("song{}.artist.0", i);
+            let score = if i % 10 == 0 { 1_000_000 } else { 950_000 };
+            let acc = if score == 1_000_000 { 100.0 } else { 97.0 };
+            record.set(
+                &song,
+                Difficulty::IN,
+                ChartRecord {
+                    score,
+                    acc,
+                    fc: true,
+                },
+            );
+            table.insert(song, [None, None, Some(10.0 + i as f32 / 10.0), None, None]);
+        }
+        (record, table)
+    }
+
+    // rks 需与报告中的列表一致
+    #[test]
+    fn rks_matches_lists() {
+        let (record, table) = record();
+        for n in [B19, B27, B30] {
+            let report = best_n(&record, &table, n);
+            assert_eq!(report.best.len(), n);
+            assert_eq!(report.phi.len(), BEST_PHI);
+            let sum: f32 = report.best.iter().chain(&report.phi).map(|e| e.rks).sum();
+            let expected = sum / (n + BEST_PHI) as f32;
+            assert!((report.rks - expected).abs() < 1e-4, "n = {}", n);
+        }
+    }
+
+    #[test]
+    fn b27_matches_player_rks() {
+        let (record, table) = record();
+        let report = best_n(&record, &table, B27);
+        assert_eq!(report.rks, crate::rks::compute(&record, &table));
+    }
+}
@@ -1,18 +1,29 @@
+use crate::best_n::{B27, best_n};
+use crate::chart_table::ChartTable;
+use crate::diff::Diff;
+use crate::error::{ErrorCode, PscError};
 use crate::game_key::{field::GameKey, serde::SerializableGameKey};
 use crate::game_progress::{field::GameProgress, serde::SerializableGameProgress};
 use crate::game_record::{field::GameRecord, serde::SerializableGameRecord};
+use crate::payload::Payload;
 use crate::settings::{field::Settings, serde::SerializableSettings};
 use crate::summary::{field::Summary, serde::SerializableSummary};
 use crate::user::{field::User, serde::SerializableUser};
-use shua_struct::{BinaryField, BitSlice, Lsb0};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
 use std::alloc::{Layout, alloc, dealloc};
+use std::cell::Cell;
+#[cfg(debug_assertions)]
+use std::collections::BTreeMap;
 use std::sync::Mutex;
 
 thread_local! {
     static LAST_ERROR: Mutex<String> = const { Mutex::new(String::new()) };
+    static LAST_ERROR_CODE: Cell<i32> = const { Cell::new(ErrorCode::Ok as i32) };
 }
 
-fn set_error(msg: &str) {
+fn set_error(code: ErrorCode, msg: &str) {
+    LAST_ERROR_CODE.with(|c| c.set(code as i32));
     LAST_ERROR.with(|err| {
         if let Ok(mut e) = err.lock() {
             e.clear();
@@ -21,6 +32,30 @@ fn set_error(msg: &str) {
     });
 }
 
+// 调用失败时的错误码与错误信息
+struct CallError {
+    code: ErrorCode,
+    message: String,
+}
+
+type CallResult = Result<Vec<u8>, CallError>;
+
+impl From<PscError> for CallError {
+    fn from(e: PscError) -> Self {
+        CallError {
+            code: e.code(),
+            message: e.to_string(),
+        }
+    }
+}
+
+fn invalid_input(message: &str) -> CallError {
+    CallError {
+        code: ErrorCode::InvalidInput,
+        message: message.to_string(),
+    }
+}
+
 #[repr(C)]
 pub struct Data {
     pub len: usize,
@@ -35,19 +70,53 @@ pub fn empty_data() -> Data {
     }
 }
 
+// 调试构建下记录每块分配的长度,用于发现重复释放与长度不符
+#[cfg(debug_assertions)]
+static ALLOCATIONS: Mutex<BTreeMap<usize, usize>> = Mutex::new(BTreeMap::new());
+
+fn track_alloc(ptr: *mut u8, len: usize) {
+    #[cfg(debug_assertions)]
+    if let Ok(mut allocs) = ALLOCATIONS.lock() {
+        allocs.insert(ptr as usize, len);
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = (ptr, len);
+}
+
+// 返回 Err 时不能释放该指针
+fn track_free(ptr: *mut u8, len: usize) -> Result<(), String> {
+    #[cfg(debug_assertions)]
+    if let Ok(mut allocs) = ALLOCATIONS.lock() {
+        match allocs.get(&(ptr as usize)) {
+            None => return Err(format!("重复释放或非本库分配的指针: {:p}", ptr)),
+            Some(&allocated) if allocated != len => {
+                return Err(format!("释放长度 {} 与分配长度 {} 不符", len, allocated));
+            }
+            Some(_) => {
+                allocs.remove(&(ptr as usize));
+            }
+        }
+    }
+    #[cfg(not(debug_assertions))]
+    let _ = (ptr, len);
+    Ok(())
+}
+
+// 空数据不分配内存,ptr 为悬垂指针,释放时直接跳过
 #[inline(always)]
-pub unsafe fn malloc_data(mut bytes: Vec<u8>) -> Data {
-    bytes.shrink_to_fit();
+pub unsafe fn malloc_data(bytes: Vec<u8>) -> Data {
     let len = bytes.len();
-    let ptr = bytes.as_mut_ptr();
-    std::mem::forget(bytes);
+    let ptr = Box::into_raw(bytes.into_boxed_slice()) as *mut u8;
+    if len > 0 {
+        track_alloc(ptr, len);
+    }
     Data { ptr, len }
 }
 
 #[unsafe(no_mangle)]
 pub extern "C" fn psc_malloc(len: usize) -> *mut u8 {
     if len == 0 {
-        set_error("无效长度");
+        set_error(ErrorCode::InvalidInput, "无效长度");
         return std::ptr::null_mut();
     }
 
@@ -55,22 +124,35 @@ pub extern "C" fn psc_malloc(len: usize) -> *mut u8 {
         let layout = match Layout::array::<u8>(len) {
             Ok(l) => l,
             Err(e) => {
-                set_error(&format!("Layout错误:{}", e));
+                set_error(ErrorCode::InvalidInput, &format!("Layout错误:{}", e));
                 return std::ptr::null_mut();
             }
         };
 
-        alloc(layout)
+        let ptr = alloc(layout);
+        if !ptr.is_null() {
+            track_alloc(ptr, len);
+        }
+        ptr
     }
 }
 
+// 空指针或长度为 0 时不做任何事
 #[unsafe(no_mangle)]
 pub extern "C" fn psc_free(ptr: *mut u8, len: usize) -> bool {
+    if ptr.is_null() || len == 0 {
+        return true;
+    }
+    if let Err(msg) = track_free(ptr, len) {
+        set_error(ErrorCode::InvalidInput, &msg);
+        return false;
+    }
+
     unsafe {
         let layout = match Layout::array::<u8>(len) {
             Ok(l) => l,
             Err(e) => {
-                set_error(&format!("Layout错误:{}", e));
+                set_error(ErrorCode::InvalidInput, &format!("Layout错误:{}", e));
                 return false;
             }
         };
@@ -79,6 +161,12 @@ pub extern "C" fn psc_free(ptr: *mut u8, len: usize) -> bool {
     }
 }
 
+// 释放本库返回的 Data,长度取自 Data 本身
+#[unsafe(no_mangle)]
+pub extern "C" fn psc_data_free(data: Data) -> bool {
+    psc_free(data.ptr, data.len)
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn psc_get_last_error() -> Data {
     LAST_ERROR.with(|err| {
@@ -94,8 +182,14 @@ pub extern "C" fn psc_get_last_error() -> Data {
     })
 }
 
+#[unsafe(no_mangle)]
+pub extern "C" fn psc_get_last_error_code() -> i32 {
+    LAST_ERROR_CODE.with(|c| c.get())
+}
+
 #[unsafe(no_mangle)]
 pub extern "C" fn psc_clear_last_error() -> bool {
+    LAST_ERROR_CODE.with(|c| c.set(ErrorCode::Ok as i32));
     LAST_ERROR.with(|err| match err.lock() {
         Ok(mut e) => {
             e.clear();
@@ -105,94 +199,686 @@ pub extern "C" fn psc_clear_last_error() -> bool {
     })
 }
 
-#[macro_export]
-macro_rules! impl_c_api {
-    ($struct_ty:ty, $serializable_ty:ty, $parse_fn:ident, $build_fn:ident) => {
-        #[unsafe(no_mangle)]
-        pub unsafe extern "C" fn $parse_fn(data_ptr: *const u8, data_len: usize) -> Data {
-            if data_ptr.is_null() || data_len == 0 {
-                set_error("空或无效的输入数据");
-                return empty_data();
+// 导出函数之间传递的序列化格式
+#[derive(Debug, Clone, Copy)]
+enum WireFormat {
+    MsgPack,
+    Json,
+}
+
+impl WireFormat {
+    fn encode<T: Serialize>(self, name: &str, value: &T) -> Result<Vec<u8>, PscError> {
+        match self {
+            WireFormat::MsgPack => {
+                rmp_serde::to_vec_named(value).map_err(|e| PscError::serialization(name, e))
             }
-            let bytes = unsafe { std::slice::from_raw_parts(data_ptr, data_len) };
-            let bits = BitSlice::<u8, Lsb0>::from_slice(bytes);
-
-            let item = match <$struct_ty>::parse(bits, &()) {
-                Ok(r) => r,
-                Err(e) => {
-                    set_error(&format!("解析错误: {:?}", e));
-                    return empty_data();
-                }
-            };
+            WireFormat::Json => {
+                serde_json::to_vec(value).map_err(|e| PscError::serialization(name, e))
+            }
+        }
+    }
 
-            let json = match rmp_serde::to_vec_named(&<$serializable_ty>::from(item)) {
-                Ok(v) => v,
-                Err(e) => {
-                    set_error(&format!("序列化错误: {:?}", e));
-                    return empty_data();
-                }
-            };
+    fn decode<T: DeserializeOwned>(self, name: &str, bytes: &[u8]) -> Result<T, PscError> {
+        match self {
+            WireFormat::MsgPack => {
+                rmp_serde::from_slice(bytes).map_err(|e| PscError::serialization(name, e))
+            }
+            WireFormat::Json => {
+                serde_json::from_slice(bytes).map_err(|e| PscError::serialization(name, e))
+            }
+        }
+    }
+}
+
+unsafe fn input<'a>(data_ptr: *const u8, data_len: usize) -> Result<&'a [u8], CallError> {
+    if data_ptr.is_null() || data_len == 0 {
+        return Err(invalid_input("空或无效的输入数据"));
+    }
+    Ok(unsafe { std::slice::from_raw_parts(data_ptr, data_len) })
+}
 
-            unsafe { malloc_data(json) }
+// 第一代接口: 失败时返回空 Data,错误信息需通过 psc_get_last_error 获取
+fn into_data(result: CallResult) -> Data {
+    match result {
+        Ok(v) => unsafe { malloc_data(v) },
+        Err(e) => {
+            set_error(e.code, &e.message);
+            empty_data()
         }
+    }
+}
+
+// 第二代接口: 返回错误码,结果写入 out,错误信息写入可选的 err_out,不修改 LAST_ERROR
+// 成功但结果为空时 out 为 {0, NULL}
+unsafe fn into_status(result: CallResult, out: *mut Data, err_out: *mut Data) -> i32 {
+    let result = if out.is_null() {
+        Err(invalid_input("out 为空指针"))
+    } else {
+        result
+    };
+
+    let (data, err, code) = match result {
+        Ok(v) if v.is_empty() => (empty_data(), empty_data(), ErrorCode::Ok),
+        Ok(v) => (unsafe { malloc_data(v) }, empty_data(), ErrorCode::Ok),
+        Err(e) => (
+            empty_data(),
+            unsafe { malloc_data(e.message.into_bytes()) },
+            e.code,
+        ),
+    };
+
+    unsafe {
+        if !out.is_null() {
+            out.write(data);
+        }
+        if !err_out.is_null() {
+            err_out.write(err);
+        } else if !err.ptr.is_null() {
+            psc_free(err.ptr, err.len);
+        }
+    }
+    code as i32
+}
 
+// 导出 $name 与带 _ex 后缀的第二代版本,$call 接收输入字节并返回结果
+#[macro_export]
+macro_rules! export_c_api {
+    ($name:ident, $call:expr) => {
         #[unsafe(no_mangle)]
-        pub unsafe extern "C" fn $build_fn(data_ptr: *const u8, data_len: usize) -> Data {
-            if data_ptr.is_null() || data_len == 0 {
-                set_error("空或无效的输入数据");
-                return empty_data();
+        pub unsafe extern "C" fn $name(data_ptr: *const u8, data_len: usize) -> Data {
+            into_data(unsafe { input(data_ptr, data_len) }.and_then($call))
+        }
+
+        const _: () = {
+            #[unsafe(export_name = concat!(stringify!($name), "_ex"))]
+            pub unsafe extern "C" fn export_ex(
+                data_ptr: *const u8,
+                data_len: usize,
+                out: *mut Data,
+                err_out: *mut Data,
+            ) -> i32 {
+                unsafe { into_status(input(data_ptr, data_len).and_then($call), out, err_out) }
             }
+        };
+    };
+}
 
-            let bytes = unsafe { std::slice::from_raw_parts(data_ptr, data_len) };
-            let serializable: $serializable_ty = match rmp_serde::from_slice(bytes) {
-                Ok(v) => v,
-                Err(e) => {
-                    set_error(&format!("反序列化错误: {:?}", e));
-                    return empty_data();
-                }
-            };
+fn parse_payload<T, S>(format: WireFormat, bytes: &[u8]) -> CallResult
+where
+    T: Payload,
+    S: Serialize + From<T>,
+{
+    let item = T::parse_bytes(bytes)?;
+    Ok(format.encode(T::NAME, &S::from(item))?)
+}
 
-            let bitvec = match <$struct_ty>::from(serializable).to_bitvec(&()) {
-                Ok(v) => v,
-                Err(e) => {
-                    set_error(&format!("构建错误: {:?}", e));
-                    return empty_data();
-                }
-            };
+fn build_payload<T, S>(format: WireFormat, bytes: &[u8]) -> CallResult
+where
+    T: Payload + From<S>,
+    S: DeserializeOwned,
+{
+    let serializable = format.decode::<S>(T::NAME, bytes)?;
+    Ok(T::from(serializable).build_bytes()?)
+}
 
-            unsafe { malloc_data(bitvec.into_vec()) }
-        }
+// 每种类型同时导出 MessagePack 与 JSON 两套函数
+#[macro_export]
+macro_rules! impl_c_api {
+    (
+        $struct_ty:ty,
+        $serializable_ty:ty,
+        $parse_fn:ident,
+        $build_fn:ident,
+        $parse_json_fn:ident,
+        $build_json_fn:ident
+    ) => {
+        export_c_api!($parse_fn, |bytes| {
+            parse_payload::<$struct_ty, $serializable_ty>(WireFormat::MsgPack, bytes)
+        });
+        export_c_api!($build_fn, |bytes| {
+            build_payload::<$struct_ty, $serializable_ty>(WireFormat::MsgPack, bytes)
+        });
+        export_c_api!($parse_json_fn, |bytes| {
+            parse_payload::<$struct_ty, $serializable_ty>(WireFormat::Json, bytes)
+        });
+        export_c_api!($build_json_fn, |bytes| {
+            build_payload::<$struct_ty, $serializable_ty>(WireFormat::Json, bytes)
+        });
+    };
+}
+
+#[cfg(feature = "encryption")]
+#[macro_export]
+macro_rules! impl_c_api_encrypted {
+    ($struct_ty:ty, $serializable_ty:ty, $decrypt_fn:ident, $encrypt_fn:ident) => {
+        export_c_api!($decrypt_fn, |bytes| {
+            let item = <$struct_ty>::parse_encrypted(bytes)?;
+            Ok(WireFormat::MsgPack.encode(<$struct_ty>::NAME, &<$serializable_ty>::from(item))?)
+        });
+        export_c_api!($encrypt_fn, |bytes| {
+            let serializable: $serializable_ty =
+                WireFormat::MsgPack.decode(<$struct_ty>::NAME, bytes)?;
+            Ok(<$struct_ty>::from(serializable).build_encrypted()?)
+        });
+    };
+}
+
+#[derive(Deserialize)]
+struct DiffRequest<T> {
+    old: T,
+    new: T,
+}
+
+#[macro_export]
+macro_rules! impl_c_api_diff {
+    ($struct_ty:ty, $serializable_ty:ty, $diff_fn:ident) => {
+        export_c_api!($diff_fn, |bytes| {
+            let request: DiffRequest<$serializable_ty> =
+                WireFormat::MsgPack.decode(<$struct_ty>::NAME, bytes)?;
+            let old = <$struct_ty>::from(request.old);
+            let new = <$struct_ty>::from(request.new);
+            Ok(WireFormat::MsgPack.encode(<$struct_ty>::NAME, &old.diff(&new))?)
+        });
     };
 }
 
-impl_c_api!(User, SerializableUser, psc_parse_user, psc_build_user);
+impl_c_api!(
+    User,
+    SerializableUser,
+    psc_parse_user,
+    psc_build_user,
+    psc_parse_user_json,
+    psc_build_user_json
+);
 impl_c_api!(
     Summary,
     SerializableSummary,
     psc_parse_summary,
-    psc_build_summary
+    psc_build_summary,
+    psc_parse_summary_json,
+    psc_build_summary_json
 );
 impl_c_api!(
     GameRecord,
     SerializableGameRecord,
     psc_parse_game_record,
-    psc_build_game_record
+    psc_build_game_record,
+    psc_parse_game_record_json,
+    psc_build_game_record_json
 );
 impl_c_api!(
     GameProgress,
     SerializableGameProgress,
     psc_parse_game_progress,
-    psc_build_game_progress
+    psc_build_game_progress,
+    psc_parse_game_progress_json,
+    psc_build_game_progress_json
 );
 impl_c_api!(
     GameKey,
     SerializableGameKey,
     psc_parse_game_key,
-    psc_build_game_key
+    psc_build_game_key,
+    psc_parse_game_key_json,
+    psc_build_game_key_json
 );
 impl_c_api!(
     Settings,
     SerializableSettings,
     psc_parse_settings,
-    psc_build_settings
+    psc_build_settings,
+    psc_parse_settings_json,
+    psc_build_settings_json
+);
+
+#[cfg(feature = "encryption")]
+impl_c_api_encrypted!(User, SerializableUser, psc_decrypt_user, psc_encrypt_user);
+#[cfg(feature = "encryption")]
+impl_c_api_encrypted!(
+    GameRecord,
+    SerializableGameRecord,
+    psc_decrypt_game_record,
+    psc_encrypt_game_record
+);
+#[cfg(feature = "encryption")]
+impl_c_api_encrypted!(
+    GameProgress,
+    SerializableGameProgress,
+    psc_decrypt_game_progress,
+    psc_encrypt_game_progress
+);
+#[cfg(feature = "encryption")]
+impl_c_api_encrypted!(
+    GameKey,
+    SerializableGameKey,
+    psc_decrypt_game_key,
+    psc_encrypt_game_key
 );
+#[cfg(feature = "encryption")]
+impl_c_api_encrypted!(
+    Settings,
+    SerializableSettings,
+    psc_decrypt_settings,
+    psc_encrypt_settings
+);
+
+impl_c_api_diff!(User, SerializableUser, psc_diff_user);
+impl_c_api_diff!(Summary, SerializableSummary, psc_diff_summary);
+impl_c_api_diff!(GameRecord, SerializableGameRecord, psc_diff_game_record);
+impl_c_api_diff!(
+    GameProgress,
+    SerializableGameProgress,
+    psc_diff_game_progress
+);
+impl_c_api_diff!(GameKey, SerializableGameKey, psc_diff_game_key);
+impl_c_api_diff!(Settings, SerializableSettings, psc_diff_settings);
+
+#[derive(Deserialize)]
+struct BestNRequest {
+    record: SerializableGameRecord,
+    constants: ChartTable,
+    #[serde(default = "default_best_n")]
+    n: usize,
+}
+
+fn default_best_n() -> usize {
+    B27
+}
+
+fn call_best_n(bytes: &[u8]) -> CallResult {
+    let request: BestNRequest = WireFormat::MsgPack.decode("best_n", bytes)?;
+    let record = GameRecord::from(request.record);
+    let report = best_n(&record, &request.constants, request.n);
+    Ok(WireFormat::MsgPack.encode("best_n", &report)?)
+}
+
+fn call_parse_summary_b64(bytes: &[u8]) -> CallResult {
+    let text =
+        std::str::from_utf8(bytes).map_err(|_| invalid_input("base64 字符串不是有效的 UTF-8"))?;
+    let summary = Summary::from_base64(text)?;
+    Ok(WireFormat::MsgPack.encode(Summary::NAME, &SerializableSummary::from(summary))?)
+}
+
+fn call_build_summary_b64(bytes: &[u8]) -> CallResult {
+    let serializable: SerializableSummary = WireFormat::MsgPack.decode(Summary::NAME, bytes)?;
+    Ok(Summary::from(serializable).to_base64()?.into_bytes())
+}
+
+export_c_api!(psc_best_n, call_best_n);
+export_c_api!(psc_parse_summary_b64, call_parse_summary_b64);
+export_c_api!(psc_build_summary_b64, call_build_summary_b64);
+
+// 导出函数的签名或行为发生不兼容变化时递增
+pub const ABI_VERSION: u32 = 2;
+
+#[derive(Serialize)]
+struct TypeCapability {
+    name: &'static str,
+    // None 表示布局不随版本变化
+    max_version: Option<u8>,
+    encrypted: bool,
+}
+
+#[derive(Serialize)]
+struct Capabilities {
+    abi_version: u32,
+    crate_version: &'static str,
+    types: Vec<TypeCapability>,
+    features: Vec<&'static str>,
+}
+
+fn type_capability<T: Payload>(encrypted: bool) -> TypeCapability {
+    TypeCapability {
+        name: T::NAME,
+        max_version: T::MAX_VERSION,
+        encrypted: encrypted && cfg!(feature = "encryption"),
+    }
+}
+
+// 引用对应的导出函数,导出被删除或改名时这里无法通过编译
+macro_rules! exported {
+    ($feature:literal, $export:ident) => {{
+        let _: unsafe extern "C" fn(*const u8, usize) -> Data = $export;
+        $feature
+    }};
+}
+
+// 只列出实际导出的功能
+fn capabilities() -> Capabilities {
+    let features = vec![
+        exported!("msgpack", psc_parse_user),
+        exported!("json", psc_parse_user_json),
+        #[cfg(feature = "encryption")]
+        exported!("encryption", psc_decrypt_user),
+        exported!("diff", psc_diff_user),
+        exported!("best_n", psc_best_n),
+        exported!("summary_b64", psc_parse_summary_b64),
+    ];
+
+    Capabilities {
+        abi_version: ABI_VERSION,
+        crate_version: env!("CARGO_PKG_VERSION"),
+        types: vec![
+            type_capability::<User>(true),
+            type_capability::<Summary>(false),
+            type_capability::<GameRecord>(true),
+            type_capability::<GameProgress>(true),
+            type_capability::<GameKey>(true),
+            type_capability::<Settings>(true),
+        ],
+        features,
+    }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn psc_abi_version() -> u32 {
+    ABI_VERSION
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn psc_crate_version() -> Data {
+    unsafe { malloc_data(env!("CARGO_PKG_VERSION").as_bytes().to_vec()) }
+}
+
+#[unsafe(no_mangle)]
+pub extern "C" fn psc_capabilities() -> Data {
+    into_data(
+        WireFormat::MsgPack
+            .encode("capabilities", &capabilities())
+            .map_err(CallError::from),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_record::song::{ChartRecord, Difficulty};
+
+    // 读出 Data 的内容并释放
+    fn take(data: Data) -> Vec<u8> {
+        let bytes = if data.len == 0 {
+            Vec::new()
+        } else {
+            unsafe { std::slice::from_raw_parts(data.ptr, data.len) }.to_vec()
+        };
+        assert!(psc_data_free(data));
+        bytes
+    }
+
+    unsafe extern "C" {
+        fn psc_parse_user_ex(
+            data_ptr: *const u8,
+            data_len: usize,
+            out: *mut Data,
+            err_out: *mut Data,
+        ) -> i32;
+    }
+
+    fn parse_user_ex(bytes: &[u8], out: *mut Data, err_out: *mut Data) -> i32 {
+        unsafe { psc_parse_user_ex(bytes.as_ptr(), bytes.len(), out, err_out) }
+    }
+
+    #[test]
+    fn ex_writes_result_to_out() {
+        let user = User {
+            version: 1,
+            self_intro: "hi".into(),
+            ..Default::default()
+        };
+        let bytes = user.build_bytes().unwrap();
+        set_error(ErrorCode::Truncated, "上一次的错误");
+
+        let (mut out, mut err) = (empty_data(), empty_data());
+        assert_eq!(parse_user_ex(&bytes, &mut out, &mut err), 0);
+        assert!(err.ptr.is_null() && err.len == 0);
+        let parsed: SerializableUser = rmp_serde::from_slice(&take(out)).unwrap();
+        assert_eq!(parsed.self_intro, "hi");
+        // 第二代接口不修改 LAST_ERROR
+        assert_eq!(psc_get_last_error_code(), ErrorCode::Truncated as i32);
+        assert!(psc_clear_last_error());
+    }
+
+    #[test]
+    fn ex_reports_error_code_and_message() {
+        let (mut out, mut err) = (empty_data(), empty_data());
+        let code = parse_user_ex(&[1], &mut out, &mut err);
+        assert_eq!(code, ErrorCode::Truncated as i32);
+        assert!(out.ptr.is_null() && out.len == 0);
+        let message = String::from_utf8(take(err)).unwrap();
+        assert!(message.starts_with("数据截断: user."), "{}", message);
+
+        let mut err = empty_data();
+        let code = unsafe { psc_parse_user_ex(std::ptr::null(), 0, &mut out, &mut err) };
+        assert_eq!(code, ErrorCode::InvalidInput as i32);
+        assert!(!take(err).is_empty());
+
+        // err_out 可以为空指针,错误信息直接释放
+        let code = parse_user_ex(&[1], &mut out, std::ptr::null_mut());
+        assert_eq!(code, ErrorCode::Truncated as i32);
+        assert_eq!(psc_get_last_error_code(), ErrorCode::Ok as i32);
+    }
+
+    #[test]
+    fn ex_rejects_null_out() {
+        let bytes = User::default().build_bytes().unwrap();
+        let mut err = empty_data();
+        let code = parse_user_ex(&bytes, std::ptr::null_mut(), &mut err);
+        assert_eq!(code, ErrorCode::InvalidInput as i32);
+        assert_eq!(String::from_utf8(take(err)).unwrap(), "out 为空指针");
+    }
+
+    // 空结果: 第二代接口写入 {0, NULL};第一代接口返回非空的悬垂指针以区别于失败,可直接释放
+    #[test]
+    fn empty_success() {
+        let (mut out, mut err) = (empty_data(), empty_data());
+        let code = unsafe { into_status(Ok(Vec::new()), &mut out, &mut err) };
+        assert_eq!(code, ErrorCode::Ok as i32);
+        assert!(out.ptr.is_null() && out.len == 0);
+        assert!(err.ptr.is_null() && err.len == 0);
+
+        let data = into_data(Ok(Vec::new()));
+        assert!(!data.ptr.is_null());
+        assert_eq!(data.len, 0);
+        assert!(psc_data_free(data));
+    }
+
+    #[cfg(debug_assertions)]
+    fn last_error() -> String {
+        String::from_utf8(take(psc_get_last_error())).unwrap()
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn double_free_is_rejected() {
+        let ptr = psc_malloc(4);
+        assert!(!ptr.is_null());
+        assert!(psc_free(ptr, 4));
+
+        assert!(!psc_free(ptr, 4));
+        assert_eq!(psc_get_last_error_code(), ErrorCode::InvalidInput as i32);
+        assert!(last_error().starts_with("重复释放或非本库分配的指针"));
+
+        let data = unsafe { malloc_data(vec![1, 2, 3]) };
+        let copy = Data {
+            len: data.len,
+            ptr: data.ptr,
+        };
+        assert!(psc_data_free(data));
+        assert!(!psc_data_free(copy));
+        assert!(psc_clear_last_error());
+    }
+
+    #[cfg(debug_assertions)]
+    #[test]
+    fn free_with_wrong_len_is_rejected() {
+        let ptr = psc_malloc(8);
+        assert!(!psc_free(ptr, 4));
+        assert_eq!(psc_get_last_error_code(), ErrorCode::InvalidInput as i32);
+        assert_eq!(last_error(), "释放长度 4 与分配长度 8 不符");
+
+        // 失败的释放不会移除记录,仍可按正确长度释放
+        assert!(psc_free(ptr, 8));
+        assert!(psc_clear_last_error());
+    }
+
+    // 同一份二进制经 MessagePack 与 JSON 两套接口得到相同的值,且都能构建回原数据
+    fn assert_formats_agree<T, S>(item: T)
+    where
+        T: Payload + From<S>,
+        S: Serialize + DeserializeOwned + From<T>,
+    {
+        let bytes = item.build_bytes().unwrap();
+        let (Ok(msgpack), Ok(json)) = (
+            parse_payload::<T, S>(WireFormat::MsgPack, &bytes),
+            parse_payload::<T, S>(WireFormat::Json, &bytes),
+        ) else {
+            panic!("{} 解析失败", T::NAME);
+        };
+        let from_msgpack: serde_json::Value = rmp_serde::from_slice(&msgpack).unwrap();
+        let from_json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(from_msgpack, from_json, "{}", T::NAME);
+
+        for (format, encoded) in [(WireFormat::MsgPack, msgpack), (WireFormat::Json, json)] {
+            let Ok(built) = build_payload::<T, S>(format, &encoded) else {
+                panic!("{} 以 {:?} 构建失败", T::NAME, format);
+            };
+            assert_eq!(built, bytes, "{} {:?}", T::NAME, format);
+        }
+    }
+
+    #[test]
+    fn json_and_msgpack_agree() {
+        use crate::game_key::field::{Key, KeyList};
+        use crate::game_progress::field::Money;
+        use crate::phi_base::{Extra, VarInt};
+
+        let mut record = GameRecord {
+            version: 1,
+            ..Default::default()
+        };
+        let chart = ChartRecord {
+            score: 995_000,
+            acc: 99.5,
+            fc: true,
+        };
+        record.set("Glaciaxion.SunsetRay.0", Difficulty::IN, chart);
+        record.set("Glaciaxion.SunsetRay.0", Difficulty::AT, chart);
+        assert_formats_agree::<_, SerializableGameRecord>(record);
+
+        let keys = vec![Key::from_flags(
+            "Glaciaxion",
+            [Some(3), Some(1), None, None, Some(1)],
+        )];
+        assert_formats_agree::<_, SerializableGameKey>(GameKey {
+            version: 3,
+            key_list: KeyList {
+                key_sum: VarInt(keys.len() as u16),
+                key_list: keys,
+            },
+            lanota_read_keys: [true, false, true, false, false, false],
+            camellia_read_key: Some(true),
+            side_story4_begin_read_key: Some(false),
+            old_score_cleared_v390: Some(true),
+            ..Default::default()
+        });
+
+        assert_formats_agree::<_, SerializableGameProgress>(GameProgress {
+            version: 4,
+            completed: "3.0".into(),
+            challenge_mode_rank: 348,
+            money: Money {
+                kib: VarInt(512),
+                mib: VarInt(3),
+                ..Default::default()
+            },
+            flag_of_song_record_key_takumi: Some([true, false, true]),
+            extra: Extra(vec![1, 2, 3]),
+            ..Default::default()
+        });
+
+        assert_formats_agree::<_, SerializableSettings>(Settings {
+            version: 1,
+            device_name: "phone".into(),
+            bright: 0.5,
+            music_volume: 0.75,
+            note_scale: 1.25,
+            ..Default::default()
+        });
+
+        assert_formats_agree::<_, SerializableUser>(User {
+            version: 1,
+            show_player_id: true,
+            self_intro: "你好".into(),
+            avatar: "Glaciaxion".into(),
+            ..Default::default()
+        });
+
+        assert_formats_agree::<_, SerializableSummary>(Summary {
+            save_version: 6,
+            challenge_mode_rank: 348,
+            rks: 12.5,
+            game_version: VarInt(103),
+            avatar: "Glaciaxion".into(),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn capabilities_match_exports() {
+        let caps: serde_json::Value = rmp_serde::from_slice(&take(psc_capabilities())).unwrap();
+        assert_eq!(caps["abi_version"], ABI_VERSION);
+        assert_eq!(psc_abi_version(), ABI_VERSION);
+        assert_eq!(caps["crate_version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(
+            String::from_utf8(take(psc_crate_version())).unwrap(),
+            env!("CARGO_PKG_VERSION")
+        );
+
+        let mut features = vec!["msgpack", "json"];
+        if cfg!(feature = "encryption") {
+            features.push("encryption");
+        }
+        features.extend(["diff", "best_n", "summary_b64"]);
+        assert_eq!(caps["features"], serde_json::json!(features));
+
+        let types = caps["types"].as_array().unwrap();
+        assert_eq!(types.len(), 6);
+        let summary = types.iter().find(|t| t["name"] == Summary::NAME).unwrap();
+        assert_eq!(summary["encrypted"], false);
+        let user = types.iter().find(|t| t["name"] == User::NAME).unwrap();
+        assert_eq!(user["encrypted"], cfg!(feature = "encryption"));
+    }
+
+    // 定数可以用 null 表示缺少的难度,也可以按难度名给出
+    #[test]
+    fn best_n_accepts_missing_constants() {
+        let mut record = GameRecord::default();
+        let chart = ChartRecord {
+            score: 1_000_000,
+            acc: 100.0,
+            fc: true,
+        };
+        record.set("a.x.0", Difficulty::Legacy, chart);
+        record.set("b.x.0", Difficulty::IN, chart);
+        let request = serde_json::json!({
+            "record": SerializableGameRecord::from(record),
+            "constants": {
+                "a.x.0": [1.0, 4.0, 8.5, null, 12.0],
+                "b.x.0": {"IN": 9.0},
+            },
+            "n": 19,
+        });
+
+        let Ok(out) = call_best_n(&rmp_serde::to_vec_named(&request).unwrap()) else {
+            panic!("best_n 调用失败");
+        };
+        let report: serde_json::Value = rmp_serde::from_slice(&out).unwrap();
+        let rks: Vec<f64> = report["best"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|e| e["rks"].as_f64().unwrap())
+            .collect();
+        assert_eq!(rks, [12.0, 9.0]);
+    }
+}
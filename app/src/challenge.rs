@@ -0,0 +1,172 @@
+use crate::game_progress::field::GameProgress;
+use crate::summary::field::Summary;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+// 课题模式等级: 百位为颜色,其余为三首曲目的等级之和,0 表示尚未挑战
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum RankColor {
+    Green = 1,
+    Blue = 2,
+    Red = 3,
+    Gold = 4,
+    Rainbow = 5,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ChallengeRank {
+    pub color: RankColor,
+    pub level: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidChallengeRank(pub u16);
+
+impl fmt::Display for InvalidChallengeRank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "无效的课题模式等级: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidChallengeRank {}
+
+impl RankColor {
+    pub fn from_digit(d: u16) -> Option<Self> {
+        match d {
+            1 => Some(RankColor::Green),
+            2 => Some(RankColor::Blue),
+            3 => Some(RankColor::Red),
+            4 => Some(RankColor::Gold),
+            5 => Some(RankColor::Rainbow),
+            _ => None,
+        }
+    }
+}
+
+impl ChallengeRank {
+    pub fn new(color: RankColor, level: u8) -> Result<Self, InvalidChallengeRank> {
+        let rank = ChallengeRank { color, level };
+        if level == 0 || level > 99 {
+            return Err(InvalidChallengeRank(rank.to_raw()));
+        }
+        Ok(rank)
+    }
+
+    // 0 表示未挑战,返回 None;raw % 100 不会超过 99,只需检查等级为 0
+    pub fn from_raw(raw: u16) -> Result<Option<Self>, InvalidChallengeRank> {
+        if raw == 0 {
+            return Ok(None);
+        }
+        let color = RankColor::from_digit(raw / 100).ok_or(InvalidChallengeRank(raw))?;
+        let level = (raw % 100) as u8;
+        if level == 0 {
+            return Err(InvalidChallengeRank(raw));
+        }
+        Ok(Some(ChallengeRank { color, level }))
+    }
+
+    pub fn to_raw(self) -> u16 {
+        self.color as u16 * 100 + self.level as u16
+    }
+}
+
+impl TryFrom<u16> for ChallengeRank {
+    type Error = InvalidChallengeRank;
+
+    fn try_from(raw: u16) -> Result<Self, Self::Error> {
+        Self::from_raw(raw)?.ok_or(InvalidChallengeRank(raw))
+    }
+}
+
+impl From<ChallengeRank> for u16 {
+    fn from(rank: ChallengeRank) -> Self {
+        rank.to_raw()
+    }
+}
+
+impl fmt::Display for ChallengeRank {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?} {}", self.color, self.level)
+    }
+}
+
+impl Summary {
+    pub fn challenge_rank(&self) -> Result<Option<ChallengeRank>, InvalidChallengeRank> {
+        ChallengeRank::from_raw(self.challenge_mode_rank)
+    }
+}
+
+impl GameProgress {
+    pub fn challenge_rank(&self) -> Result<Option<ChallengeRank>, InvalidChallengeRank> {
+        ChallengeRank::from_raw(self.challenge_mode_rank)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_color_round_trips() {
+        for (digit, color) in [
+            (1, RankColor::Green),
+            (2, RankColor::Blue),
+            (3, RankColor::Red),
+            (4, RankColor::Gold),
+            (5, RankColor::Rainbow),
+        ] {
+            let raw = digit * 100 + 48;
+            let rank = ChallengeRank::from_raw(raw).unwrap().unwrap();
+            assert_eq!(rank, ChallengeRank { color, level: 48 });
+            assert_eq!(rank.to_raw(), raw);
+            assert_eq!(ChallengeRank::try_from(raw), Ok(rank));
+        }
+    }
+
+    #[test]
+    fn zero_means_not_challenged() {
+        assert_eq!(ChallengeRank::from_raw(0), Ok(None));
+        assert_eq!(ChallengeRank::try_from(0), Err(InvalidChallengeRank(0)));
+    }
+
+    #[test]
+    fn invalid_raw_values_are_rejected() {
+        for raw in [48, 100, 500, 600, 648, 999, u16::MAX] {
+            assert_eq!(
+                ChallengeRank::from_raw(raw),
+                Err(InvalidChallengeRank(raw)),
+                "{}",
+                raw
+            );
+        }
+        assert!(ChallengeRank::from_raw(599).is_ok());
+    }
+
+    #[test]
+    fn new_checks_level_range() {
+        assert!(ChallengeRank::new(RankColor::Gold, 1).is_ok());
+        assert!(ChallengeRank::new(RankColor::Gold, 99).is_ok());
+        assert_eq!(
+            ChallengeRank::new(RankColor::Gold, 0),
+            Err(InvalidChallengeRank(400))
+        );
+        assert!(ChallengeRank::new(RankColor::Gold, 100).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn serializes_color_in_snake_case() {
+        let rank = ChallengeRank {
+            color: RankColor::Rainbow,
+            level: 45,
+        };
+        let json = serde_json::to_string(&rank).unwrap();
+        assert_eq!(json, r#"{"color":"rainbow","level":45}"#);
+        assert_eq!(serde_json::from_str::<ChallengeRank>(&json).unwrap(), rank);
+    }
+}
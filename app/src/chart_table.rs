@@ -0,0 +1,411 @@
+use crate::game_record::field::{DIFF_ORDER, GameRecord};
+use crate::game_record::song::Difficulty;
+use crate::rks::{ChartConstants, song_levels};
+use std::collections::BTreeMap;
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+pub type Constants = [Option<f32>; 5];
+
+#[derive(Debug, Clone, Default)]
+pub struct ChartTable {
+    charts: BTreeMap<String, Constants>,
+}
+
+#[derive(Debug)]
+pub enum ChartTableError {
+    InvalidNumber {
+        line: usize,
+        column: usize,
+        value: String,
+    },
+    TooManyColumns {
+        line: usize,
+    },
+    UnknownDifficulty(String),
+    DuplicateSong(String),
+    Json(String),
+}
+
+impl fmt::Display for ChartTableError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChartTableError::InvalidNumber {
+                line,
+                column,
+                value,
+            } => write!(f, "第 {} 行第 {} 列不是有效定数: {}", line, column, value),
+            ChartTableError::TooManyColumns { line } => write!(f, "第 {} 行列数过多", line),
+            ChartTableError::UnknownDifficulty(d) => write!(f, "未知难度: {}", d),
+            ChartTableError::DuplicateSong(id) => write!(f, "曲目重复: {}", id),
+            ChartTableError::Json(msg) => write!(f, "JSON 错误: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ChartTableError {}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum ChartWarning {
+    UnknownSong {
+        song_id: String,
+    },
+    MissingConstant {
+        song_id: String,
+        difficulty: Difficulty,
+    },
+}
+
+impl ChartTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, song_id: impl Into<String>, constants: Constants) {
+        self.charts.insert(song_id.into(), constants);
+    }
+
+    pub fn get(&self, song_id: &str) -> Option<&Constants> {
+        self.charts.get(song_id)
+    }
+
+    pub fn len(&self) -> usize {
+        self.charts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.charts.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &Constants)> {
+        self.charts.iter().map(|(k, v)| (k.as_str(), v))
+    }
+
+    // 同一曲目出现多次时报错,不让后出现的静默覆盖前者
+    fn insert_new(&mut self, song_id: &str, constants: Constants) -> Result<(), ChartTableError> {
+        if self.charts.contains_key(song_id) {
+            return Err(ChartTableError::DuplicateSong(song_id.to_string()));
+        }
+        self.insert(song_id, constants);
+        Ok(())
+    }
+
+    // 每行: 曲目id,EZ,HD,IN,AT[,Legacy],分隔符为逗号或制表符
+    // 首行含有难度名时视为表头,按表头确定各列对应的难度
+    pub fn from_csv(text: &str) -> Result<Self, ChartTableError> {
+        let mut table = ChartTable::new();
+        let mut order = Difficulty::ALL.to_vec();
+        let mut first = true;
+
+        for (i, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let sep = if line.contains('\t') { '\t' } else { ',' };
+            let mut columns = line.split(sep).map(str::trim);
+            let song_id = columns.next().unwrap_or_default();
+            let values: Vec<&str> = columns.collect();
+
+            if std::mem::take(&mut first) && values.iter().any(|v| DIFF_ORDER.contains(v)) {
+                order = values
+                    .iter()
+                    .map(|v| {
+                        v.parse()
+                            .map_err(|_| ChartTableError::UnknownDifficulty(v.to_string()))
+                    })
+                    .collect::<Result<_, _>>()?;
+                continue;
+            }
+
+            if values.len() > order.len() {
+                return Err(ChartTableError::TooManyColumns { line: i + 1 });
+            }
+
+            let mut constants: Constants = [None; 5];
+            for (j, value) in values.iter().enumerate() {
+                if value.is_empty() {
+                    continue;
+                }
+                let v = value
+                    .parse::<f32>()
+                    .map_err(|_| ChartTableError::InvalidNumber {
+                        line: i + 1,
+                        column: j + 2,
+                        value: value.to_string(),
+                    })?;
+                constants[order[j].index()] = Some(v);
+            }
+
+            table.insert_new(song_id, constants)?;
+        }
+
+        Ok(table)
+    }
+
+    pub fn validate(&self, record: &GameRecord) -> Vec<ChartWarning> {
+        let mut warnings = Vec::new();
+
+        for song in &record.song_list {
+            let Some(constants) = self.charts.get(&song.name.0) else {
+                warnings.push(ChartWarning::UnknownSong {
+                    song_id: song.name.0.clone(),
+                });
+                continue;
+            };
+
+            // Legacy 谱面没有定数是正常的
+            for (difficulty, _) in song_levels(song) {
+                if difficulty != Difficulty::Legacy && constants[difficulty.index()].is_none() {
+                    warnings.push(ChartWarning::MissingConstant {
+                        song_id: song.name.0.clone(),
+                        difficulty,
+                    });
+                }
+            }
+        }
+
+        warnings
+    }
+}
+
+// 支持 {"id": [1.0, 4.0, 8.5]} 与 {"id": {"EZ": 1.0, "IN": 8.5}} 两种写法,列表中可用 null 表示缺少的难度
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(untagged)]
+enum RawConstants {
+    List(Vec<Option<f32>>),
+    Map(BTreeMap<String, f32>),
+}
+
+#[cfg(feature = "serde")]
+impl RawConstants {
+    fn into_constants(self, song_id: &str) -> Result<Constants, ChartTableError> {
+        let mut constants: Constants = [None; 5];
+        match self {
+            RawConstants::List(list) => {
+                if list.len() > DIFF_ORDER.len() {
+                    return Err(ChartTableError::Json(format!("{} 定数过多", song_id)));
+                }
+                for (i, v) in list.into_iter().enumerate() {
+                    constants[i] = v;
+                }
+            }
+            RawConstants::Map(map) => {
+                for (diff, v) in map {
+                    let d: Difficulty = diff
+                        .parse()
+                        .map_err(|_| ChartTableError::UnknownDifficulty(diff))?;
+                    constants[d.index()] = Some(v);
+                }
+            }
+        }
+        Ok(constants)
+    }
+}
+
+// 按出现顺序保留所有条目,以便发现重复的曲目 id
+#[cfg(feature = "serde")]
+struct RawTable(Vec<(String, RawConstants)>);
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for RawTable {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        struct Visitor;
+
+        impl<'de> serde::de::Visitor<'de> for Visitor {
+            type Value = RawTable;
+
+            fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str("曲目 id 到定数的映射")
+            }
+
+            fn visit_map<A: serde::de::MapAccess<'de>>(
+                self,
+                mut map: A,
+            ) -> Result<RawTable, A::Error> {
+                let mut entries = Vec::new();
+                while let Some(entry) = map.next_entry()? {
+                    entries.push(entry);
+                }
+                Ok(RawTable(entries))
+            }
+        }
+
+        d.deserialize_map(Visitor)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl RawTable {
+    fn into_table(self) -> Result<ChartTable, ChartTableError> {
+        let mut table = ChartTable::new();
+        for (song_id, value) in self.0 {
+            let constants = value.into_constants(&song_id)?;
+            table.insert_new(&song_id, constants)?;
+        }
+        Ok(table)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for ChartTable {
+    fn deserialize<D: serde::Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        RawTable::deserialize(d)?
+            .into_table()
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(feature = "json")]
+impl ChartTable {
+    pub fn from_json(text: &str) -> Result<Self, ChartTableError> {
+        serde_json::from_str::<RawTable>(text)
+            .map_err(|e| ChartTableError::Json(e.to_string()))?
+            .into_table()
+    }
+}
+
+impl ChartConstants for ChartTable {
+    fn constant(&self, song_id: &str, difficulty: Difficulty) -> Option<f32> {
+        self.charts.get(song_id)?[difficulty.index()]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_record::song::ChartRecord;
+
+    #[test]
+    fn csv_without_header() {
+        let table =
+            ChartTable::from_csv("# 注释\nA.X.0,1.5,4,8.5\n\nB.Y.0, 2 ,,11.2,15.1,9\n").unwrap();
+        assert_eq!(table.len(), 2);
+        assert_eq!(
+            table.get("A.X.0"),
+            Some(&[Some(1.5), Some(4.0), Some(8.5), None, None])
+        );
+        assert_eq!(
+            table.get("B.Y.0"),
+            Some(&[Some(2.0), None, Some(11.2), Some(15.1), Some(9.0)])
+        );
+    }
+
+    #[test]
+    fn tsv_with_header_maps_columns() {
+        let table = ChartTable::from_csv("id\tIN\tAT\nA.X.0\t12.5\t15.8\n").unwrap();
+        assert_eq!(table.len(), 1);
+        assert_eq!(
+            table.get("A.X.0"),
+            Some(&[None, None, Some(12.5), Some(15.8), None])
+        );
+        assert_eq!(table.constant("A.X.0", Difficulty::AT), Some(15.8));
+
+        let table = ChartTable::from_csv("id,EZ,HD,IN,AT,Legacy\nA.X.0,1,2,3,4,5\n").unwrap();
+        assert_eq!(
+            table.get("A.X.0").unwrap()[Difficulty::Legacy.index()],
+            Some(5.0)
+        );
+    }
+
+    #[test]
+    fn csv_errors() {
+        assert!(matches!(
+            ChartTable::from_csv("id,EZ,SP\nA.X.0,1,2\n"),
+            Err(ChartTableError::UnknownDifficulty(d)) if d == "SP"
+        ));
+        assert!(matches!(
+            ChartTable::from_csv("A.X.0,1,2\nB.Y.0,1,x.5\n"),
+            Err(ChartTableError::InvalidNumber { line: 2, column: 3, value }) if value == "x.5"
+        ));
+        // 表头只能出现在第一行
+        assert!(matches!(
+            ChartTable::from_csv("A.X.0,1\nid,EZ\n"),
+            Err(ChartTableError::InvalidNumber {
+                line: 2,
+                column: 2,
+                ..
+            })
+        ));
+        assert!(matches!(
+            ChartTable::from_csv("id,IN\nA.X.0,1,2\n"),
+            Err(ChartTableError::TooManyColumns { line: 2 })
+        ));
+        assert!(matches!(
+            ChartTable::from_csv("A.X.0,1\nB.Y.0,2\nA.X.0,3\n"),
+            Err(ChartTableError::DuplicateSong(id)) if id == "A.X.0"
+        ));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_list_and_map() {
+        let table = ChartTable::from_json(
+            r#"{"A.X.0": [1.0, null, 8.5], "B.Y.0": {"IN": 12.0, "Legacy": 9.5}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            table.get("A.X.0"),
+            Some(&[Some(1.0), None, Some(8.5), None, None])
+        );
+        assert_eq!(
+            table.get("B.Y.0"),
+            Some(&[None, None, Some(12.0), None, Some(9.5)])
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_errors() {
+        assert!(matches!(
+            ChartTable::from_json(r#"{"A.X.0": {"SP": 1.0}}"#),
+            Err(ChartTableError::UnknownDifficulty(d)) if d == "SP"
+        ));
+        assert!(matches!(
+            ChartTable::from_json(r#"{"A.X.0": [1, 2, 3, 4, 5, 6]}"#),
+            Err(ChartTableError::Json(_))
+        ));
+        assert!(matches!(
+            ChartTable::from_json(r#"{"A.X.0": ["1.0"]}"#),
+            Err(ChartTableError::Json(_))
+        ));
+        assert!(matches!(
+            ChartTable::from_json(r#"{"A.X.0": [1.0], "A.X.0": [2.0]}"#),
+            Err(ChartTableError::DuplicateSong(id)) if id == "A.X.0"
+        ));
+    }
+
+    #[test]
+    fn validate_reports_unknown_songs_and_missing_constants() {
+        let table = ChartTable::from_csv("A.X.0,1,,8.5\n").unwrap();
+        let chart = ChartRecord {
+            score: 900_000,
+            acc: 95.0,
+            fc: false,
+        };
+        let mut record = GameRecord::default();
+        record.set("A.X.0", Difficulty::EZ, chart);
+        record.set("A.X.0", Difficulty::HD, chart);
+        record.set("A.X.0", Difficulty::Legacy, chart);
+        record.set("B.Y.0", Difficulty::IN, chart);
+
+        assert_eq!(
+            table.validate(&record),
+            vec![
+                ChartWarning::MissingConstant {
+                    song_id: "A.X.0".to_string(),
+                    difficulty: Difficulty::HD,
+                },
+                ChartWarning::UnknownSong {
+                    song_id: "B.Y.0".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,809 @@
+use crate::game_key::field::{GameKey, KEY_FIELDS, Key};
+use crate::game_progress::field::{GameProgress, Money};
+use crate::game_record::field::GameRecord;
+use crate::game_record::song::{ChartRecord, Difficulty};
+use crate::settings::field::Settings;
+use crate::summary::field::{Level, Summary};
+use crate::user::field::User;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::Serialize;
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(untagged))]
+pub enum Value {
+    None,
+    Bool(bool),
+    Int(i64),
+    Float(f32),
+    Str(String),
+    Bytes(Vec<u8>),
+}
+
+impl From<bool> for Value {
+    fn from(v: bool) -> Self {
+        Value::Bool(v)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(v: u8) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<u16> for Value {
+    fn from(v: u16) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(v: u32) -> Self {
+        Value::Int(v as i64)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(v: i64) -> Self {
+        Value::Int(v)
+    }
+}
+
+impl From<f32> for Value {
+    fn from(v: f32) -> Self {
+        Value::Float(v)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(v: &str) -> Self {
+        Value::Str(v.to_string())
+    }
+}
+
+impl From<&[u8]> for Value {
+    fn from(v: &[u8]) -> Self {
+        Value::Bytes(v.to_vec())
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(v: Option<T>) -> Self {
+        v.map_or(Value::None, Into::into)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum Change {
+    SongAdded {
+        song_id: String,
+    },
+    SongRemoved {
+        song_id: String,
+    },
+    LevelAdded {
+        song_id: String,
+        difficulty: Difficulty,
+        score: u32,
+        acc: f32,
+        fc: bool,
+    },
+    LevelRemoved {
+        song_id: String,
+        difficulty: Difficulty,
+    },
+    ScoreChanged {
+        song_id: String,
+        difficulty: Difficulty,
+        old: u32,
+        new: u32,
+    },
+    AccChanged {
+        song_id: String,
+        difficulty: Difficulty,
+        old: f32,
+        new: f32,
+    },
+    FcChanged {
+        song_id: String,
+        difficulty: Difficulty,
+        old: bool,
+        new: bool,
+    },
+    KeyAdded {
+        key: String,
+    },
+    KeyRemoved {
+        key: String,
+    },
+    KeyChanged {
+        key: String,
+        field: &'static str,
+        old: Option<u8>,
+        new: Option<u8>,
+    },
+    // delta_kib 按换算成 KiB 后的总额计算,进位不会产生负数分量
+    MoneyChanged {
+        old: [u16; 5],
+        new: [u16; 5],
+        delta_kib: i64,
+    },
+    FieldChanged {
+        path: String,
+        old: Value,
+        new: Value,
+    },
+}
+
+pub trait Diff {
+    fn diff(&self, new: &Self) -> Vec<Change>;
+}
+
+struct Recorder {
+    root: &'static str,
+    changes: Vec<Change>,
+}
+
+impl Recorder {
+    fn new(root: &'static str) -> Self {
+        Recorder {
+            root,
+            changes: Vec::new(),
+        }
+    }
+
+    fn field<V: Into<Value> + PartialEq>(&mut self, name: &str, old: V, new: V) {
+        if old != new {
+            self.changes.push(Change::FieldChanged {
+                path: format!("{}.{}", self.root, name),
+                old: old.into(),
+                new: new.into(),
+            });
+        }
+    }
+
+    fn flags(&mut self, name: &str, old: &[bool], new: &[bool]) {
+        for (i, (o, n)) in old.iter().zip(new.iter()).enumerate() {
+            self.field(&format!("{}[{}]", name, i), *o, *n);
+        }
+    }
+
+    fn extra(&mut self, old: &[u8], new: &[u8]) {
+        self.field("extra", old, new);
+    }
+}
+
+fn levels_of(record: &GameRecord) -> BTreeMap<&str, BTreeMap<Difficulty, ChartRecord>> {
+    record
+        .song_list
+        .iter()
+        .map(|song| (song.name.0.as_str(), song.iter_levels().collect()))
+        .collect()
+}
+
+impl Diff for GameRecord {
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut r = Recorder::new("game_record");
+        r.field("version", self.version, new.version);
+
+        let old_songs = levels_of(self);
+        let new_songs = levels_of(new);
+
+        for song_id in old_songs.keys().filter(|k| !new_songs.contains_key(*k)) {
+            r.changes.push(Change::SongRemoved {
+                song_id: song_id.to_string(),
+            });
+        }
+
+        for (song_id, new_levels) in &new_songs {
+            let old_levels = match old_songs.get(song_id) {
+                Some(l) => l,
+                None => {
+                    r.changes.push(Change::SongAdded {
+                        song_id: song_id.to_string(),
+                    });
+                    &BTreeMap::new()
+                }
+            };
+
+            for difficulty in old_levels.keys().filter(|d| !new_levels.contains_key(*d)) {
+                r.changes.push(Change::LevelRemoved {
+                    song_id: song_id.to_string(),
+                    difficulty: *difficulty,
+                });
+            }
+
+            for (&difficulty, level) in new_levels {
+                let song_id = song_id.to_string();
+                let Some(old_level) = old_levels.get(&difficulty) else {
+                    r.changes.push(Change::LevelAdded {
+                        song_id,
+                        difficulty,
+                        score: level.score,
+                        acc: level.acc,
+                        fc: level.fc,
+                    });
+                    continue;
+                };
+
+                if old_level.score != level.score {
+                    r.changes.push(Change::ScoreChanged {
+                        song_id: song_id.clone(),
+                        difficulty,
+                        old: old_level.score,
+                        new: level.score,
+                    });
+                }
+                if old_level.acc != level.acc {
+                    r.changes.push(Change::AccChanged {
+                        song_id: song_id.clone(),
+                        difficulty,
+                        old: old_level.acc,
+                        new: level.acc,
+                    });
+                }
+                if old_level.fc != level.fc {
+                    r.changes.push(Change::FcChanged {
+                        song_id,
+                        difficulty,
+                        old: old_level.fc,
+                        new: level.fc,
+                    });
+                }
+            }
+        }
+
+        r.extra(&self.extra.0, &new.extra.0);
+        r.changes
+    }
+}
+
+impl Diff for GameKey {
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut r = Recorder::new("game_key");
+        r.field("version", self.version, new.version);
+
+        let old_keys: BTreeMap<&str, &Key> = self
+            .key_list
+            .key_list
+            .iter()
+            .map(|k| (k.key.0.as_str(), k))
+            .collect();
+        let new_keys: BTreeMap<&str, &Key> = new
+            .key_list
+            .key_list
+            .iter()
+            .map(|k| (k.key.0.as_str(), k))
+            .collect();
+
+        for key in old_keys.keys().filter(|k| !new_keys.contains_key(*k)) {
+            r.changes.push(Change::KeyRemoved {
+                key: key.to_string(),
+            });
+        }
+        for (key, new_key) in &new_keys {
+            let Some(old_key) = old_keys.get(key) else {
+                r.changes.push(Change::KeyAdded {
+                    key: key.to_string(),
+                });
+                continue;
+            };
+            let old_flags = old_key.flags();
+            let new_flags = new_key.flags();
+            for (i, field) in KEY_FIELDS.iter().enumerate() {
+                if old_flags[i] != new_flags[i] {
+                    r.changes.push(Change::KeyChanged {
+                        key: key.to_string(),
+                        field,
+                        old: old_flags[i],
+                        new: new_flags[i],
+                    });
+                }
+            }
+        }
+
+        r.flags(
+            "lanota_read_keys",
+            &self.lanota_read_keys,
+            &new.lanota_read_keys,
+        );
+        r.field(
+            "camellia_read_key",
+            self.camellia_read_key,
+            new.camellia_read_key,
+        );
+        r.field(
+            "side_story4_begin_read_key",
+            self.side_story4_begin_read_key,
+            new.side_story4_begin_read_key,
+        );
+        r.field(
+            "old_score_cleared_v390",
+            self.old_score_cleared_v390,
+            new.old_score_cleared_v390,
+        );
+        r.extra(&self.extra.0, &new.extra.0);
+        r.changes
+    }
+}
+
+fn money_kib(m: &Money) -> i64 {
+    (m.to_bytes() / 1024) as i64
+}
+
+impl Diff for GameProgress {
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut r = Recorder::new("game_progress");
+        r.field("version", self.version, new.version);
+        r.field(
+            "base.is_first_run",
+            self.base.is_first_run,
+            new.base.is_first_run,
+        );
+        r.field(
+            "base.legacy_chapter_finished",
+            self.base.legacy_chapter_finished,
+            new.base.legacy_chapter_finished,
+        );
+        r.field(
+            "base.already_show_collection_tip",
+            self.base.already_show_collection_tip,
+            new.base.already_show_collection_tip,
+        );
+        r.field(
+            "base.already_show_auto_unlock_in_tip",
+            self.base.already_show_auto_unlock_in_tip,
+            new.base.already_show_auto_unlock_in_tip,
+        );
+        r.field(
+            "completed",
+            self.completed.0.as_str(),
+            new.completed.0.as_str(),
+        );
+        r.field(
+            "song_update_info",
+            self.song_update_info.0,
+            new.song_update_info.0,
+        );
+        r.field(
+            "challenge_mode_rank",
+            self.challenge_mode_rank,
+            new.challenge_mode_rank,
+        );
+
+        let old_money = self.money.units();
+        let new_money = new.money.units();
+        if old_money != new_money {
+            r.changes.push(Change::MoneyChanged {
+                old: old_money,
+                new: new_money,
+                delta_kib: money_kib(&new.money) - money_kib(&self.money),
+            });
+        }
+
+        r.flags(
+            "unlock_flag_of_spasmodic",
+            &self.unlock_flag_of_spasmodic,
+            &new.unlock_flag_of_spasmodic,
+        );
+        r.flags(
+            "unlock_flag_of_igallta",
+            &self.unlock_flag_of_igallta,
+            &new.unlock_flag_of_igallta,
+        );
+        r.flags(
+            "unlock_flag_of_rrharil",
+            &self.unlock_flag_of_rrharil,
+            &new.unlock_flag_of_rrharil,
+        );
+        r.flags(
+            "flag_of_song_record_key",
+            &self.flag_of_song_record_key,
+            &new.flag_of_song_record_key,
+        );
+        r.flags(
+            "random_version_unlocked",
+            &self.random_version_unlocked,
+            &new.random_version_unlocked,
+        );
+        r.field(
+            "chapter8_base.unlock_begin",
+            self.chapter8_base.unlock_begin,
+            new.chapter8_base.unlock_begin,
+        );
+        r.field(
+            "chapter8_base.unlock_second_phase",
+            self.chapter8_base.unlock_second_phase,
+            new.chapter8_base.unlock_second_phase,
+        );
+        r.field(
+            "chapter8_base.passed",
+            self.chapter8_base.passed,
+            new.chapter8_base.passed,
+        );
+        r.flags(
+            "chapter8_song_unlocked",
+            &self.chapter8_song_unlocked,
+            &new.chapter8_song_unlocked,
+        );
+        match (
+            &self.flag_of_song_record_key_takumi,
+            &new.flag_of_song_record_key_takumi,
+        ) {
+            (Some(old), Some(new)) => r.flags("flag_of_song_record_key_takumi", old, new),
+            (old, new) => r.field(
+                "flag_of_song_record_key_takumi",
+                old.is_some(),
+                new.is_some(),
+            ),
+        }
+        r.extra(&self.extra.0, &new.extra.0);
+        r.changes
+    }
+}
+
+impl Diff for Settings {
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut r = Recorder::new("settings");
+        r.field("version", self.version, new.version);
+        r.field(
+            "base.chord_support",
+            self.base.chord_support,
+            new.base.chord_support,
+        );
+        r.field(
+            "base.fc_ap_indicator",
+            self.base.fc_ap_indicator,
+            new.base.fc_ap_indicator,
+        );
+        r.field(
+            "base.enable_hit_sound",
+            self.base.enable_hit_sound,
+            new.base.enable_hit_sound,
+        );
+        r.field(
+            "base.low_resolution_mode",
+            self.base.low_resolution_mode,
+            new.base.low_resolution_mode,
+        );
+        r.field(
+            "device_name",
+            self.device_name.0.as_str(),
+            new.device_name.0.as_str(),
+        );
+        r.field("bright", self.bright, new.bright);
+        r.field("music_volume", self.music_volume, new.music_volume);
+        r.field("effect_volume", self.effect_volume, new.effect_volume);
+        r.field(
+            "hit_sound_volume",
+            self.hit_sound_volume,
+            new.hit_sound_volume,
+        );
+        r.field("sound_offset", self.sound_offset, new.sound_offset);
+        r.field("note_scale", self.note_scale, new.note_scale);
+        r.extra(&self.extra.0, &new.extra.0);
+        r.changes
+    }
+}
+
+impl Diff for User {
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut r = Recorder::new("user");
+        r.field("version", self.version, new.version);
+        r.field("show_player_id", self.show_player_id, new.show_player_id);
+        r.field(
+            "self_intro",
+            self.self_intro.0.as_str(),
+            new.self_intro.0.as_str(),
+        );
+        r.field("avatar", self.avatar.0.as_str(), new.avatar.0.as_str());
+        r.field(
+            "background",
+            self.background.0.as_str(),
+            new.background.0.as_str(),
+        );
+        r.extra(&self.extra.0, &new.extra.0);
+        r.changes
+    }
+}
+
+impl Diff for Summary {
+    fn diff(&self, new: &Self) -> Vec<Change> {
+        let mut r = Recorder::new("summary");
+        r.field("save_version", self.save_version, new.save_version);
+        r.field(
+            "challenge_mode_rank",
+            self.challenge_mode_rank,
+            new.challenge_mode_rank,
+        );
+        r.field("rks", self.rks, new.rks);
+        r.field("game_version", self.game_version.0, new.game_version.0);
+        r.field("avatar", self.avatar.0.as_str(), new.avatar.0.as_str());
+
+        let levels: [(&str, &Level, &Level); 4] = [
+            ("ez", &self.level.ez, &new.level.ez),
+            ("hd", &self.level.hd, &new.level.hd),
+            ("in", &self.level.r#in, &new.level.r#in),
+            ("at", &self.level.at, &new.level.at),
+        ];
+        for (name, old, new) in levels {
+            r.field(&format!("level.{}.clear", name), old.clear, new.clear);
+            r.field(&format!("level.{}.fc", name), old.fc, new.fc);
+            r.field(&format!("level.{}.phi", name), old.phi, new.phi);
+        }
+        r.extra(&self.extra.0, &new.extra.0);
+        r.changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::phi_base::VarInt;
+
+    fn chart(score: u32, acc: f32, fc: bool) -> ChartRecord {
+        ChartRecord { score, acc, fc }
+    }
+
+    fn field(path: &str, old: impl Into<Value>, new: impl Into<Value>) -> Change {
+        Change::FieldChanged {
+            path: path.to_string(),
+            old: old.into(),
+            new: new.into(),
+        }
+    }
+
+    fn record() -> GameRecord {
+        let mut record = GameRecord::default();
+        record.set("A.X.0", Difficulty::EZ, chart(900_000, 95.0, false));
+        record.set("A.X.0", Difficulty::HD, chart(800_000, 90.0, false));
+        record.set("B.Y.0", Difficulty::IN, chart(700_000, 80.0, false));
+        record
+    }
+
+    fn game_key(keys: Vec<Key>) -> GameKey {
+        GameKey {
+            key_list: crate::game_key::field::KeyList {
+                key_sum: VarInt(keys.len() as u16),
+                key_list: keys,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn money(kib: u16, mib: u16) -> Money {
+        Money {
+            kib: VarInt(kib),
+            mib: VarInt(mib),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn identical_inputs_have_no_changes() {
+        assert!(record().diff(&record()).is_empty());
+        let key = || {
+            game_key(vec![Key::from_flags(
+                "k",
+                [Some(1), None, None, None, None],
+            )])
+        };
+        assert!(key().diff(&key()).is_empty());
+        assert!(
+            GameProgress::default()
+                .diff(&GameProgress::default())
+                .is_empty()
+        );
+        assert!(Settings::default().diff(&Settings::default()).is_empty());
+        assert!(User::default().diff(&User::default()).is_empty());
+        assert!(Summary::default().diff(&Summary::default()).is_empty());
+    }
+
+    #[test]
+    fn game_record_changes() {
+        let old = record();
+        let mut new = record();
+        new.set("A.X.0", Difficulty::EZ, chart(950_000, 97.5, true));
+        new.remove("A.X.0", Difficulty::HD);
+        new.set("A.X.0", Difficulty::AT, chart(600_000, 70.0, false));
+        new.remove("B.Y.0", Difficulty::IN);
+        new.set("C.Z.0", Difficulty::IN, chart(1_000_000, 100.0, true));
+        new.version = 1;
+
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                field("game_record.version", 0u8, 1u8),
+                Change::SongRemoved {
+                    song_id: "B.Y.0".to_string(),
+                },
+                Change::LevelRemoved {
+                    song_id: "A.X.0".to_string(),
+                    difficulty: Difficulty::HD,
+                },
+                Change::ScoreChanged {
+                    song_id: "A.X.0".to_string(),
+                    difficulty: Difficulty::EZ,
+                    old: 900_000,
+                    new: 950_000,
+                },
+                Change::AccChanged {
+                    song_id: "A.X.0".to_string(),
+                    difficulty: Difficulty::EZ,
+                    old: 95.0,
+                    new: 97.5,
+                },
+                Change::FcChanged {
+                    song_id: "A.X.0".to_string(),
+                    difficulty: Difficulty::EZ,
+                    old: false,
+                    new: true,
+                },
+                Change::LevelAdded {
+                    song_id: "A.X.0".to_string(),
+                    difficulty: Difficulty::AT,
+                    score: 600_000,
+                    acc: 70.0,
+                    fc: false,
+                },
+                Change::SongAdded {
+                    song_id: "C.Z.0".to_string(),
+                },
+                Change::LevelAdded {
+                    song_id: "C.Z.0".to_string(),
+                    difficulty: Difficulty::IN,
+                    score: 1_000_000,
+                    acc: 100.0,
+                    fc: true,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn game_key_changes() {
+        let old = game_key(vec![
+            Key::from_flags("gone", [Some(1), None, None, None, None]),
+            Key::from_flags("kept", [Some(1), Some(0), None, None, None]),
+        ]);
+        let mut new = game_key(vec![
+            Key::from_flags("kept", [Some(3), None, None, None, Some(1)]),
+            Key::from_flags("new", [None, Some(1), None, None, None]),
+        ]);
+        new.lanota_read_keys[4] = true;
+        new.camellia_read_key = Some(true);
+
+        let key_changed = |field, old, new| Change::KeyChanged {
+            key: "kept".to_string(),
+            field,
+            old,
+            new,
+        };
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                Change::KeyRemoved {
+                    key: "gone".to_string(),
+                },
+                key_changed("read_collection_piece_num", Some(1), Some(3)),
+                key_changed("unlock_single", Some(0), None),
+                key_changed("unlock_avatar", None, Some(1)),
+                Change::KeyAdded {
+                    key: "new".to_string(),
+                },
+                field("game_key.lanota_read_keys[4]", false, true),
+                field("game_key.camellia_read_key", Value::None, true),
+            ]
+        );
+    }
+
+    #[test]
+    fn game_progress_changes() {
+        let old = GameProgress {
+            version: 3,
+            money: money(1023, 0),
+            ..Default::default()
+        };
+        let mut new = GameProgress {
+            version: 4,
+            money: money(0, 1),
+            flag_of_song_record_key_takumi: Some([false; 3]),
+            ..Default::default()
+        };
+        new.completed = "3.0".into();
+        new.unlock_flag_of_igallta[2] = true;
+        new.chapter8_base.passed = true;
+        new.chapter8_song_unlocked[5] = true;
+
+        assert_eq!(
+            old.diff(&new),
+            vec![
+                field("game_progress.version", 3u8, 4u8),
+                field("game_progress.completed", "", "3.0"),
+                // KiB 进位到 MiB 时总额只增加 1 KiB
+                Change::MoneyChanged {
+                    old: [1023, 0, 0, 0, 0],
+                    new: [0, 1, 0, 0, 0],
+                    delta_kib: 1,
+                },
+                field("game_progress.unlock_flag_of_igallta[2]", false, true),
+                field("game_progress.chapter8_base.passed", false, true),
+                field("game_progress.chapter8_song_unlocked[5]", false, true),
+                field("game_progress.flag_of_song_record_key_takumi", false, true),
+            ]
+        );
+
+        let mut newer = GameProgress {
+            version: 4,
+            money: money(0, 0),
+            flag_of_song_record_key_takumi: Some([false, false, true]),
+            ..Default::default()
+        };
+        newer.completed = "3.0".into();
+        newer.unlock_flag_of_igallta[2] = true;
+        newer.chapter8_base.passed = true;
+        newer.chapter8_song_unlocked[5] = true;
+        assert_eq!(
+            new.diff(&newer),
+            vec![
+                Change::MoneyChanged {
+                    old: [0, 1, 0, 0, 0],
+                    new: [0; 5],
+                    delta_kib: -1024,
+                },
+                field(
+                    "game_progress.flag_of_song_record_key_takumi[2]",
+                    false,
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn settings_user_and_summary_changes() {
+        let new = Settings {
+            device_name: "phone".into(),
+            note_scale: 1.5,
+            ..Default::default()
+        };
+        assert_eq!(
+            Settings::default().diff(&new),
+            vec![
+                field("settings.device_name", "", "phone"),
+                field("settings.note_scale", 0.0f32, 1.5f32),
+            ]
+        );
+
+        let new = User {
+            show_player_id: true,
+            extra: crate::phi_base::Extra(vec![1, 2]),
+            ..Default::default()
+        };
+        assert_eq!(
+            User::default().diff(&new),
+            vec![
+                field("user.show_player_id", false, true),
+                field("user.extra", &[][..], &[1u8, 2][..]),
+            ]
+        );
+
+        let mut new = Summary {
+            rks: 12.5,
+            ..Default::default()
+        };
+        new.level.r#in.phi = 3;
+        assert_eq!(
+            Summary::default().diff(&new),
+            vec![
+                field("summary.rks", 0.0f32, 12.5f32),
+                field("summary.level.in.phi", 0u16, 3u16),
+            ]
+        );
+    }
+}
@@ -0,0 +1,203 @@
+use crate::error::PscError;
+use crate::game_key::field::GameKey;
+use crate::game_progress::field::GameProgress;
+use crate::game_record::field::GameRecord;
+use crate::payload::Payload;
+use crate::settings::field::Settings;
+use crate::user::field::User;
+use aes::Aes256;
+use cbc::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit, block_padding::Pkcs7};
+
+const KEY: [u8; 32] = [
+    0xe8, 0x96, 0x9a, 0xd2, 0xa5, 0x40, 0x25, 0x9b, 0x97, 0x91, 0x90, 0x8b, 0x88, 0xe6, 0xbf, 0x03,
+    0x1e, 0x6d, 0x21, 0x95, 0x6e, 0xfa, 0xd6, 0x8a, 0x50, 0xdd, 0x55, 0xd6, 0x7a, 0xb0, 0x92, 0x4b,
+];
+const IV: [u8; 16] = [
+    0x2a, 0x4f, 0xf0, 0x8a, 0xc8, 0x0d, 0x63, 0x07, 0x00, 0x57, 0xc5, 0x95, 0x18, 0xc8, 0x32, 0x53,
+];
+
+type Aes256CbcDec = cbc::Decryptor<Aes256>;
+type Aes256CbcEnc = cbc::Encryptor<Aes256>;
+
+fn encryption_error(root: &str, field: &str, bit_offset: usize, message: &str) -> PscError {
+    PscError::Encryption {
+        path: format!("{}.{}", root, field),
+        bit_offset,
+        message: message.to_string(),
+    }
+}
+
+// 条目格式: 1 字节版本前缀 + AES-256-CBC(PKCS#7) 密文,解密后保留前缀
+fn decrypt(root: &str, data: &[u8]) -> Result<Vec<u8>, PscError> {
+    let (&prefix, body) = data
+        .split_first()
+        .ok_or_else(|| encryption_error(root, "prefix", 0, "缺少版本前缀"))?;
+    let plain = Aes256CbcDec::new(&KEY.into(), &IV.into())
+        .decrypt_padded_vec_mut::<Pkcs7>(body)
+        .map_err(|_| encryption_error(root, "body", 8, "密文长度或填充无效"))?;
+
+    let mut out = Vec::with_capacity(plain.len() + 1);
+    out.push(prefix);
+    out.extend_from_slice(&plain);
+    Ok(out)
+}
+
+fn encrypt(root: &str, data: &[u8]) -> Result<Vec<u8>, PscError> {
+    let (&prefix, body) = data
+        .split_first()
+        .ok_or_else(|| encryption_error(root, "prefix", 0, "缺少版本前缀"))?;
+    let cipher = Aes256CbcEnc::new(&KEY.into(), &IV.into()).encrypt_padded_vec_mut::<Pkcs7>(body);
+
+    let mut out = Vec::with_capacity(cipher.len() + 1);
+    out.push(prefix);
+    out.extend_from_slice(&cipher);
+    Ok(out)
+}
+
+pub fn decrypt_entry(data: &[u8]) -> Result<Vec<u8>, PscError> {
+    decrypt("entry", data)
+}
+
+pub fn encrypt_entry(data: &[u8]) -> Result<Vec<u8>, PscError> {
+    encrypt("entry", data)
+}
+
+pub fn parse_encrypted<T: Payload>(data: &[u8]) -> Result<T, PscError> {
+    T::parse_bytes(&decrypt(T::NAME, data)?)
+}
+
+pub fn build_encrypted<T: Payload>(item: &T) -> Result<Vec<u8>, PscError> {
+    encrypt(T::NAME, &item.build_bytes()?)
+}
+
+macro_rules! impl_encrypted {
+    ($struct_ty:ty) => {
+        impl $struct_ty {
+            pub fn parse_encrypted(data: &[u8]) -> Result<Self, PscError> {
+                parse_encrypted(data)
+            }
+
+            pub fn build_encrypted(&self) -> Result<Vec<u8>, PscError> {
+                build_encrypted(self)
+            }
+        }
+    };
+}
+
+impl_encrypted!(GameRecord);
+impl_encrypted!(GameKey);
+impl_encrypted!(GameProgress);
+impl_encrypted!(User);
+impl_encrypted!(Settings);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::game_record::song::{ChartRecord, Difficulty};
+    use crate::payload::ParseOptions;
+
+    // 由 openssl enc -aes-256-cbc 使用同一组 key/iv 加密 "phigros" 得到
+    const PHIGROS_CIPHER: [u8; 16] = [
+        0x41, 0x9f, 0x36, 0xbf, 0xe3, 0x41, 0x9a, 0x8e, 0x91, 0x4e, 0x33, 0x36, 0x8a, 0xcc, 0xef,
+        0xf2,
+    ];
+    // 16 个 0 字节不带填充加密,解密后最后一字节为 0,不是合法的 PKCS#7 填充
+    const ZERO_BLOCK_CIPHER: [u8; 16] = [
+        0x79, 0x92, 0x53, 0x75, 0x47, 0x35, 0xf8, 0xf6, 0x7b, 0x5b, 0xf0, 0xd7, 0xa6, 0xcd, 0x3b,
+        0x10,
+    ];
+
+    fn round_trip<T: Payload>(item: &T) {
+        let plain = item.build_bytes().unwrap();
+        let encrypted = build_encrypted(item).unwrap();
+        assert_eq!(encrypted[0], plain[0]);
+        assert_ne!(&encrypted[1..], &plain[1..]);
+        let parsed: T = parse_encrypted(&encrypted).unwrap();
+        assert_eq!(parsed.build_bytes().unwrap(), plain);
+    }
+
+    #[test]
+    fn every_type_round_trips() {
+        let mut record = GameRecord {
+            version: 1,
+            ..Default::default()
+        };
+        record.set(
+            "Glaciaxion.SunsetRay.0",
+            Difficulty::IN,
+            ChartRecord {
+                score: 991_234,
+                acc: 99.1,
+                fc: true,
+            },
+        );
+        round_trip(&record);
+        round_trip(&GameKey {
+            version: 1,
+            ..Default::default()
+        });
+        round_trip(&GameProgress {
+            version: 4,
+            flag_of_song_record_key_takumi: Some([true, false, true]),
+            ..Default::default()
+        });
+        round_trip(&User {
+            version: 1,
+            self_intro: "你好".into(),
+            ..Default::default()
+        });
+        round_trip(&Settings {
+            version: 1,
+            device_name: "phone".into(),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn known_ciphertext() {
+        let mut entry = vec![0x01];
+        entry.extend_from_slice(&PHIGROS_CIPHER);
+        assert_eq!(decrypt_entry(&entry).unwrap(), b"\x01phigros");
+        assert_eq!(encrypt_entry(b"\x01phigros").unwrap(), entry);
+    }
+
+    #[test]
+    fn prefix_is_kept_and_not_authenticated() {
+        let mut entry = vec![0xFF];
+        entry.extend_from_slice(&PHIGROS_CIPHER);
+        assert_eq!(decrypt_entry(&entry).unwrap(), b"\xFFphigros");
+
+        // 前缀是明文的版本号,改动后只能由严格模式的版本检查发现
+        let mut encrypted = User::default().build_encrypted().unwrap();
+        encrypted[0] = 0xFF;
+        let plain = decrypt_entry(&encrypted).unwrap();
+        let err = User::parse_with(&plain, &ParseOptions::strict()).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::UnsupportedVersion);
+    }
+
+    fn assert_encryption_error(result: Result<Vec<u8>, PscError>, path: &str) {
+        let err = result.unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Encryption);
+        assert_eq!(err.path(), path);
+    }
+
+    #[test]
+    fn invalid_input_returns_errors() {
+        assert_encryption_error(decrypt_entry(&[]), "entry.prefix");
+        assert_encryption_error(encrypt_entry(&[]), "entry.prefix");
+        // 只有前缀,没有密文
+        assert_encryption_error(decrypt_entry(&[1]), "entry.body");
+        // 长度不是块大小的整数倍
+        assert_encryption_error(decrypt_entry(&PHIGROS_CIPHER[..10]), "entry.body");
+        assert_encryption_error(decrypt_entry(&[0x01; 18]), "entry.body");
+
+        let mut bad_padding = vec![0x01];
+        bad_padding.extend_from_slice(&ZERO_BLOCK_CIPHER);
+        assert_encryption_error(decrypt_entry(&bad_padding), "entry.body");
+
+        let err = GameRecord::parse_encrypted(&bad_padding).unwrap_err();
+        assert_eq!(err.path(), "game_record.body");
+        assert_eq!(err.bit_offset(), Some(8));
+    }
+}
@@ -0,0 +1,284 @@
+use crate::layout::FieldSpan;
+use shua_struct::BinaryError;
+use std::fmt;
+
+#[repr(i32)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCode {
+    Ok = 0,
+    InvalidInput = 1,
+    Truncated = 2,
+    InvalidString = 3,
+    VarIntOutOfRange = 4,
+    UnsupportedVersion = 5,
+    Serialization = 6,
+    Encryption = 7,
+    TrailingData = 8,
+    NonCanonicalVarInt = 9,
+    InvalidValue = 10,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PscError {
+    Truncated {
+        path: String,
+        bit_offset: usize,
+        needed: usize,
+        available: usize,
+    },
+    InvalidString {
+        path: String,
+        bit_offset: usize,
+    },
+    VarIntOutOfRange {
+        path: String,
+        bit_offset: usize,
+    },
+    UnsupportedVersion {
+        path: String,
+        bit_offset: usize,
+        version: u8,
+        max: u8,
+    },
+    // 构建或 serde 出错时没有位置信息,bit_offset 为 None
+    Serialization {
+        path: String,
+        bit_offset: Option<usize>,
+        message: String,
+    },
+    TrailingData {
+        path: String,
+        bit_offset: usize,
+        len: usize,
+    },
+    NonCanonicalVarInt {
+        path: String,
+        bit_offset: usize,
+        value: u16,
+    },
+    InvalidValue {
+        path: String,
+        bit_offset: usize,
+        message: String,
+    },
+    Encryption {
+        path: String,
+        bit_offset: usize,
+        message: String,
+    },
+}
+
+impl PscError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            PscError::Truncated { .. } => ErrorCode::Truncated,
+            PscError::InvalidString { .. } => ErrorCode::InvalidString,
+            PscError::VarIntOutOfRange { .. } => ErrorCode::VarIntOutOfRange,
+            PscError::UnsupportedVersion { .. } => ErrorCode::UnsupportedVersion,
+            PscError::Serialization { .. } => ErrorCode::Serialization,
+            PscError::TrailingData { .. } => ErrorCode::TrailingData,
+            PscError::NonCanonicalVarInt { .. } => ErrorCode::NonCanonicalVarInt,
+            PscError::InvalidValue { .. } => ErrorCode::InvalidValue,
+            PscError::Encryption { .. } => ErrorCode::Encryption,
+        }
+    }
+
+    pub fn path(&self) -> &str {
+        match self {
+            PscError::Truncated { path, .. }
+            | PscError::InvalidString { path, .. }
+            | PscError::VarIntOutOfRange { path, .. }
+            | PscError::UnsupportedVersion { path, .. }
+            | PscError::Serialization { path, .. }
+            | PscError::TrailingData { path, .. }
+            | PscError::NonCanonicalVarInt { path, .. }
+            | PscError::InvalidValue { path, .. }
+            | PscError::Encryption { path, .. } => path,
+        }
+    }
+
+    pub fn bit_offset(&self) -> Option<usize> {
+        match self {
+            PscError::Serialization { bit_offset, .. } => *bit_offset,
+            PscError::Truncated { bit_offset, .. }
+            | PscError::InvalidString { bit_offset, .. }
+            | PscError::VarIntOutOfRange { bit_offset, .. }
+            | PscError::UnsupportedVersion { bit_offset, .. }
+            | PscError::TrailingData { bit_offset, .. }
+            | PscError::NonCanonicalVarInt { bit_offset, .. }
+            | PscError::InvalidValue { bit_offset, .. }
+            | PscError::Encryption { bit_offset, .. } => Some(*bit_offset),
+        }
+    }
+
+    pub fn serialization(path: impl Into<String>, message: impl fmt::Debug) -> Self {
+        PscError::Serialization {
+            path: path.into(),
+            bit_offset: None,
+            message: format!("{:?}", message),
+        }
+    }
+}
+
+pub trait IntoPscError {
+    // layout 为解析时 Walker 走出的布局,用于定位出错字段;构建时传入 None
+    fn into_psc_error(self, root: &str, layout: Option<&FieldSpan>) -> PscError;
+}
+
+// 派生宏只给出出错的顶层字段,位置取该字段在 layout 中的起点,找不到时取 layout 的末尾
+impl<S: fmt::Debug, I, C: fmt::Debug> IntoPscError for BinaryError<S, I, C> {
+    fn into_psc_error(self, root: &str, layout: Option<&FieldSpan>) -> PscError {
+        let offset_of = |field: Option<&str>| {
+            layout.map(|l| {
+                field
+                    .and_then(|f| l.find(f))
+                    .map_or(l.bit_offset + l.bit_len, |span| span.bit_offset)
+            })
+        };
+        match self {
+            BinaryError::At { source, .. } => {
+                let field = format!("{:?}", source);
+                PscError::Serialization {
+                    bit_offset: offset_of(Some(&field)),
+                    path: format!("{}.{}", root, field),
+                    message: "字段编解码失败".to_string(),
+                }
+            }
+            BinaryError::BitCountMismatch { needed, got } => PscError::Serialization {
+                path: root.to_string(),
+                bit_offset: offset_of(None),
+                message: format!("位数不匹配: 需要 {}, 实际 {}", needed, got),
+            },
+            BinaryError::Custom(c) => PscError::Serialization {
+                path: root.to_string(),
+                bit_offset: offset_of(None),
+                message: format!("{:?}", c),
+            },
+        }
+    }
+}
+
+impl fmt::Display for PscError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PscError::Truncated {
+                path,
+                bit_offset,
+                needed,
+                available,
+            } => write!(
+                f,
+                "数据截断: {} (第 {} 位, 需要 {} 位, 剩余 {} 位)",
+                path, bit_offset, needed, available
+            ),
+            PscError::InvalidString { path, bit_offset } => {
+                write!(f, "无效的 UTF-8 字符串: {} (第 {} 位)", path, bit_offset)
+            }
+            PscError::VarIntOutOfRange { path, bit_offset } => {
+                write!(f, "VarInt 超出范围: {} (第 {} 位)", path, bit_offset)
+            }
+            PscError::UnsupportedVersion {
+                path,
+                bit_offset,
+                version,
+                max,
+            } => write!(
+                f,
+                "不支持的版本: {} = {} (最高支持 {}, 第 {} 位)",
+                path, version, max, bit_offset
+            ),
+            PscError::Serialization {
+                path,
+                bit_offset: Some(bit_offset),
+                message,
+            } => write!(
+                f,
+                "序列化错误: {} (第 {} 位): {}",
+                path, bit_offset, message
+            ),
+            PscError::Serialization {
+                path,
+                bit_offset: None,
+                message,
+            } => write!(f, "序列化错误: {}: {}", path, message),
+            PscError::TrailingData {
+                path,
+                bit_offset,
+                len,
+            } => write!(
+                f,
+                "存在多余数据: {} (第 {} 位, 共 {} 字节)",
+                path, bit_offset, len
+            ),
+            PscError::NonCanonicalVarInt {
+                path,
+                bit_offset,
+                value,
+            } => write!(
+                f,
+                "VarInt 编码不规范: {} = {} (第 {} 位)",
+                path, value, bit_offset
+            ),
+            PscError::InvalidValue {
+                path,
+                bit_offset,
+                message,
+            } => write!(f, "无效的值: {} (第 {} 位): {}", path, bit_offset, message),
+            PscError::Encryption {
+                path,
+                bit_offset,
+                message,
+            } => write!(
+                f,
+                "加解密错误: {} (第 {} 位): {}",
+                path, bit_offset, message
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PscError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::{Payload, Walker};
+    use crate::user::field::{User, UserError};
+
+    fn user_layout() -> FieldSpan {
+        let bytes = User {
+            version: 1,
+            avatar: "Introduction".into(),
+            ..Default::default()
+        }
+        .build_bytes()
+        .unwrap();
+        let mut w = Walker::new(User::NAME, &bytes);
+        User::walk(&mut w).unwrap();
+        w.into_layout()
+    }
+
+    // 派生宏的错误按 walk 的布局定位到出错字段的起点
+    #[test]
+    fn binary_error_is_located_by_layout() {
+        let layout = user_layout();
+        let avatar = layout.find("avatar").unwrap().bit_offset;
+
+        let err: BinaryError<UserError, &str, ()> = BinaryError::At {
+            index: "avatar",
+            source: UserError::avatar,
+        };
+        let err = err.into_psc_error(User::NAME, Some(&layout));
+        assert_eq!(err.path(), "user.avatar");
+        assert_eq!(err.bit_offset(), Some(avatar));
+
+        let err: BinaryError<UserError, &str, ()> = BinaryError::bit_count_mismatch(8, 0);
+        let err = err.into_psc_error(User::NAME, Some(&layout));
+        assert_eq!(err.bit_offset(), Some(layout.bit_len));
+
+        let err: BinaryError<UserError, &str, ()> = BinaryError::Custom(());
+        let err = err.into_psc_error(User::NAME, None);
+        assert_eq!(err.bit_offset(), None);
+        assert!(!err.to_string().contains("位"));
+    }
+}
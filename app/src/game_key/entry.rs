@@ -0,0 +1,357 @@
+use super::field::{GameKey, Key};
+use crate::phi_base::{PhiString, VarInt};
+use std::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum KeyKind {
+    Song,
+    Collection,
+    Illustration,
+    Avatar,
+}
+
+// 与 KEY_FIELDS 顺序一致,布尔值在 flag 中以 0/1 存储
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct KeyEntry {
+    pub read_collection_piece_num: Option<u8>,
+    pub unlock_single: Option<bool>,
+    pub unlock_collection_piece_num: Option<u8>,
+    pub unlock_illustration: Option<bool>,
+    pub unlock_avatar: Option<bool>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidKey {
+    pub key: String,
+    pub flag_len: usize,
+    pub expected: usize,
+}
+
+impl fmt::Display for InvalidKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "key {} 的 flag 长度为 {}, 与类型位数 {} 不符",
+            self.key, self.flag_len, self.expected
+        )
+    }
+}
+
+impl std::error::Error for InvalidKey {}
+
+impl KeyEntry {
+    pub fn song(unlocked: bool) -> Self {
+        KeyEntry {
+            unlock_single: Some(unlocked),
+            ..Default::default()
+        }
+    }
+
+    pub fn collection(read: u8, unlocked: u8) -> Self {
+        KeyEntry {
+            read_collection_piece_num: Some(read),
+            unlock_collection_piece_num: Some(unlocked),
+            ..Default::default()
+        }
+    }
+
+    pub fn illustration(unlocked: bool) -> Self {
+        KeyEntry {
+            unlock_illustration: Some(unlocked),
+            ..Default::default()
+        }
+    }
+
+    pub fn avatar(unlocked: bool) -> Self {
+        KeyEntry {
+            unlock_avatar: Some(unlocked),
+            ..Default::default()
+        }
+    }
+
+    pub fn from_flags(flags: [Option<u8>; 5]) -> Self {
+        KeyEntry {
+            read_collection_piece_num: flags[0],
+            unlock_single: flags[1].map(|v| v == 1),
+            unlock_collection_piece_num: flags[2],
+            unlock_illustration: flags[3].map(|v| v == 1),
+            unlock_avatar: flags[4].map(|v| v == 1),
+        }
+    }
+
+    pub fn to_flags(&self) -> [Option<u8>; 5] {
+        [
+            self.read_collection_piece_num,
+            self.unlock_single.map(u8::from),
+            self.unlock_collection_piece_num,
+            self.unlock_illustration.map(u8::from),
+            self.unlock_avatar.map(u8::from),
+        ]
+    }
+
+    // 一个 key 可以同时属于多个类别
+    pub fn kinds(&self) -> Vec<KeyKind> {
+        [
+            (KeyKind::Song, self.unlock_single.is_some()),
+            (
+                KeyKind::Collection,
+                self.read_collection_piece_num.is_some()
+                    || self.unlock_collection_piece_num.is_some(),
+            ),
+            (KeyKind::Illustration, self.unlock_illustration.is_some()),
+            (KeyKind::Avatar, self.unlock_avatar.is_some()),
+        ]
+        .into_iter()
+        .filter_map(|(kind, has)| has.then_some(kind))
+        .collect()
+    }
+
+    pub fn is_kind(&self, kind: KeyKind) -> bool {
+        self.kinds().contains(&kind)
+    }
+
+    // 收藏品至少解锁一个碎片即视为已解锁
+    pub fn is_unlocked(&self, kind: KeyKind) -> bool {
+        match kind {
+            KeyKind::Song => self.unlock_single == Some(true),
+            KeyKind::Collection => self.unlock_collection_piece_num.is_some_and(|n| n > 0),
+            KeyKind::Illustration => self.unlock_illustration == Some(true),
+            KeyKind::Avatar => self.unlock_avatar == Some(true),
+        }
+    }
+}
+
+impl Key {
+    pub fn new(key: impl Into<PhiString>, entry: KeyEntry) -> Self {
+        Key::from_flags(key, entry.to_flags())
+    }
+
+    pub fn entry(&self) -> KeyEntry {
+        KeyEntry::from_flags(self.flags())
+    }
+
+    // 重新生成 type、flag 与 length,三者始终保持一致
+    pub fn set_entry(&mut self, entry: KeyEntry) {
+        let key = std::mem::take(&mut self.key);
+        *self = Key::new(key, entry);
+    }
+
+    pub fn update(&mut self, f: impl FnOnce(&mut KeyEntry)) {
+        let mut entry = self.entry();
+        f(&mut entry);
+        self.set_entry(entry);
+    }
+
+    pub fn set_read_collection_piece_num(&mut self, v: Option<u8>) {
+        self.update(|e| e.read_collection_piece_num = v);
+    }
+
+    pub fn set_unlock_single(&mut self, v: Option<bool>) {
+        self.update(|e| e.unlock_single = v);
+    }
+
+    pub fn set_unlock_collection_piece_num(&mut self, v: Option<u8>) {
+        self.update(|e| e.unlock_collection_piece_num = v);
+    }
+
+    pub fn set_unlock_illustration(&mut self, v: Option<bool>) {
+        self.update(|e| e.unlock_illustration = v);
+    }
+
+    pub fn set_unlock_avatar(&mut self, v: Option<bool>) {
+        self.update(|e| e.unlock_avatar = v);
+    }
+
+    pub fn type_bits(&self) -> usize {
+        [
+            self.r#type.exist_read_collection_piece_num,
+            self.r#type.exist_unlcok_single,
+            self.r#type.exist_unlock_collection_piece_num,
+            self.r#type.exist_unlock_illustration,
+            self.r#type.exist_unlock_avatar,
+        ]
+        .iter()
+        .filter(|b| **b)
+        .count()
+    }
+
+    // flag 长度需等于 type 中置位的个数,length 需等于 flag 长度加 1
+    pub fn validate(&self) -> Result<(), InvalidKey> {
+        let expected = self.type_bits();
+        if self.flag.len() != expected || self.length as usize != self.flag.len() + 1 {
+            return Err(InvalidKey {
+                key: self.key.0.clone(),
+                flag_len: self.flag.len(),
+                expected,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl GameKey {
+    pub fn key(&self, name: &str) -> Option<&Key> {
+        self.key_list.key_list.iter().find(|k| k.key.0 == name)
+    }
+
+    pub fn get(&self, name: &str) -> Option<KeyEntry> {
+        self.key(name).map(Key::entry)
+    }
+
+    // key 不存在时追加到末尾
+    pub fn set(&mut self, name: impl AsRef<str>, entry: KeyEntry) {
+        let name = name.as_ref();
+        let list = &mut self.key_list.key_list;
+        match list.iter_mut().find(|k| k.key.0 == name) {
+            Some(k) => k.set_entry(entry),
+            None => {
+                list.push(Key::new(name, entry));
+                self.key_list.key_sum = VarInt(list.len() as u16);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<KeyEntry> {
+        let list = &mut self.key_list.key_list;
+        let i = list.iter().position(|k| k.key.0 == name)?;
+        let key = list.remove(i);
+        self.key_list.key_sum = VarInt(list.len() as u16);
+        Some(key.entry())
+    }
+
+    pub fn iter_entries(&self) -> impl Iterator<Item = (&str, KeyEntry)> + '_ {
+        self.key_list
+            .key_list
+            .iter()
+            .map(|k| (k.key.0.as_str(), k.entry()))
+    }
+
+    pub fn unlocked(&self, kind: KeyKind) -> impl Iterator<Item = &str> + '_ {
+        self.iter_entries()
+            .filter(move |(_, e)| e.is_unlocked(kind))
+            .map(|(name, _)| name)
+    }
+
+    pub fn validate(&self) -> Vec<InvalidKey> {
+        self.key_list
+            .key_list
+            .iter()
+            .filter_map(|k| k.validate().err())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::payload::{ParseOptions, Payload, Walker};
+
+    fn game_key() -> GameKey {
+        let mut key = GameKey::default();
+        key.set("Glaciaxion", KeyEntry::song(true));
+        key.set("Introduction", KeyEntry::collection(2, 0));
+        key.set("Shadow", KeyEntry::illustration(false));
+        key.set("Avatar", KeyEntry::avatar(true));
+        key
+    }
+
+    #[test]
+    fn each_kind_is_recognized_and_valid() {
+        let key = game_key();
+        assert!(key.validate().is_empty());
+        for (name, kind) in [
+            ("Glaciaxion", KeyKind::Song),
+            ("Introduction", KeyKind::Collection),
+            ("Shadow", KeyKind::Illustration),
+            ("Avatar", KeyKind::Avatar),
+        ] {
+            let entry = key.get(name).unwrap();
+            assert_eq!(entry.kinds(), vec![kind]);
+            assert_eq!(
+                key.key(name).unwrap().type_bits(),
+                1 + (kind == KeyKind::Collection) as usize
+            );
+        }
+
+        assert_eq!(
+            key.unlocked(KeyKind::Song).collect::<Vec<_>>(),
+            ["Glaciaxion"]
+        );
+        assert_eq!(
+            key.unlocked(KeyKind::Avatar).collect::<Vec<_>>(),
+            ["Avatar"]
+        );
+        // 收藏品一个碎片都未解锁时不算解锁
+        assert_eq!(key.unlocked(KeyKind::Collection).count(), 0);
+        assert_eq!(key.unlocked(KeyKind::Illustration).count(), 0);
+    }
+
+    #[test]
+    fn multi_kind_entry() {
+        let entry = KeyEntry {
+            unlock_single: Some(true),
+            unlock_collection_piece_num: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(entry.kinds(), vec![KeyKind::Song, KeyKind::Collection]);
+        assert!(entry.is_unlocked(KeyKind::Song));
+        assert!(entry.is_unlocked(KeyKind::Collection));
+        assert!(!entry.is_kind(KeyKind::Avatar));
+        assert_eq!(KeyEntry::from_flags(entry.to_flags()), entry);
+    }
+
+    // type 字节中 5 位之外的位属于对齐填充,解析时忽略;布尔 flag 只有 1 表示解锁
+    #[test]
+    fn out_of_range_bits_are_ignored() {
+        let mut bytes = game_key().build_bytes().unwrap();
+        let mut w = Walker::new(GameKey::NAME, &bytes);
+        GameKey::walk(&mut w).unwrap();
+        let layout = w.into_layout();
+        let r#type = layout.find("key_list.key_list[0].type").unwrap().bit_offset / 8;
+        let flag = layout.find("key_list.key_list[0].flag").unwrap().bit_offset / 8;
+        bytes[r#type] |= 0b1110_0000;
+        bytes[flag] = 2;
+
+        let key = GameKey::parse_with(&bytes, &ParseOptions::strict()).unwrap();
+        let glaciaxion = key.key("Glaciaxion").unwrap();
+        assert!(glaciaxion.validate().is_ok());
+        assert_eq!(glaciaxion.entry().kinds(), vec![KeyKind::Song]);
+        assert_eq!(glaciaxion.flags()[1], Some(2));
+        assert!(!glaciaxion.entry().is_unlocked(KeyKind::Song));
+    }
+
+    // flag 长度与 type 不符的 key 只在严格模式下被 check 拒绝
+    #[test]
+    fn mismatched_flag_is_rejected_in_strict_mode() {
+        let mut key = game_key();
+        let broken = &mut key.key_list.key_list[2];
+        broken.flag.push(1);
+        broken.length += 1;
+        assert_eq!(
+            key.validate(),
+            vec![InvalidKey {
+                key: "Shadow".to_string(),
+                flag_len: 2,
+                expected: 1,
+            }]
+        );
+        let bytes = key.build_bytes().unwrap();
+
+        let parsed = GameKey::parse_bytes(&bytes).unwrap();
+        assert_eq!(parsed.validate().len(), 1);
+
+        let err = GameKey::parse_with(&bytes, &ParseOptions::strict()).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidValue);
+        assert_eq!(err.path(), "game_key.key_list.key_list[2].flag");
+
+        let mut w = Walker::new(GameKey::NAME, &bytes);
+        GameKey::walk(&mut w).unwrap();
+        let flag = w
+            .into_layout()
+            .find("key_list.key_list[2].flag")
+            .unwrap()
+            .bit_offset;
+        assert_eq!(err.bit_offset(), Some(flag));
+    }
+}
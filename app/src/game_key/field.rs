@@ -1,6 +1,14 @@
 use crate::phi_base::*;
 use shua_struct::BinaryField;
 
+pub static KEY_FIELDS: [&str; 5] = [
+    "read_collection_piece_num",
+    "unlock_single",
+    "unlock_collection_piece_num",
+    "unlock_illustration",
+    "unlock_avatar",
+];
+
 #[derive(Debug, Default, BinaryField)]
 #[binary_struct(bit_order = shua_struct::Lsb0)]
 pub struct KeyType {
@@ -25,6 +33,37 @@ impl Key {
     fn get_flag_len(&self) -> usize {
         (self.length).saturating_sub(1) as usize
     }
+
+    // flag 按 KeyType 中置位的顺序依次排列,布尔值以 0/1 存储
+    pub fn flags(&self) -> [Option<u8>; 5] {
+        let exists = [
+            self.r#type.exist_read_collection_piece_num,
+            self.r#type.exist_unlcok_single,
+            self.r#type.exist_unlock_collection_piece_num,
+            self.r#type.exist_unlock_illustration,
+            self.r#type.exist_unlock_avatar,
+        ];
+        let mut flag = self.flag.iter();
+        exists.map(|e| if e { flag.next().copied() } else { None })
+    }
+
+    pub fn from_flags(key: impl Into<PhiString>, flags: [Option<u8>; 5]) -> Self {
+        let r#type = KeyType {
+            exist_read_collection_piece_num: flags[0].is_some(),
+            exist_unlcok_single: flags[1].is_some(),
+            exist_unlock_collection_piece_num: flags[2].is_some(),
+            exist_unlock_illustration: flags[3].is_some(),
+            exist_unlock_avatar: flags[4].is_some(),
+        };
+        let flag: Vec<u8> = flags.into_iter().flatten().collect();
+
+        Key {
+            key: key.into(),
+            length: flag.len() as u8 + 1,
+            r#type,
+            flag,
+        }
+    }
 }
 
 #[derive(Debug, Default, BinaryField)]
@@ -48,6 +87,7 @@ pub struct GameKey {
     pub side_story4_begin_read_key: Option<bool>,
     #[binary_field(align = 8, if_func = "is_version_at_least_3")]
     pub old_score_cleared_v390: Option<bool>,
+    pub extra: Extra,
 }
 
 impl GameKey {
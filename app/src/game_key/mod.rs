@@ -1,4 +1,6 @@
+pub mod entry;
 pub mod field;
+mod payload;
 
 #[cfg(feature = "serde")]
 pub mod serde;
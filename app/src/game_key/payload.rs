@@ -0,0 +1,90 @@
+use super::field::{GameKey, KeyType};
+use crate::error::PscError;
+use crate::layout::FieldSpan;
+use crate::payload::{ParseOptions, Payload, Walker};
+use crate::phi_base::Extra;
+
+impl Payload for GameKey {
+    const NAME: &'static str = "game_key";
+    const MAX_VERSION: Option<u8> = Some(3);
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn extra(&self) -> &Extra {
+        &self.extra
+    }
+
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError> {
+        let version = w.read::<u8>("version")?;
+        w.nest("key_list", |w| {
+            let key_sum = w.varint("key_sum")?;
+            w.list("key_list", key_sum as usize, |w| {
+                w.string("key")?;
+                let length = w.read::<u8>("length")?;
+                w.read_aligned::<KeyType>("type", 8)?;
+                w.raw("flag", length.saturating_sub(1) as usize)
+            })
+        })?;
+        w.read_aligned::<[bool; 6]>("lanota_read_keys", 8)?;
+        if version >= 2 {
+            w.read_aligned::<bool>("camellia_read_key", 8)?;
+        }
+        if version >= 3 {
+            w.read_aligned::<bool>("side_story4_begin_read_key", 8)?;
+            w.read_aligned::<bool>("old_score_cleared_v390", 8)?;
+        }
+        w.rest("extra");
+        Ok(())
+    }
+
+    fn check(&self, layout: &FieldSpan, options: &ParseOptions) -> Result<(), PscError> {
+        if !options.strict {
+            return Ok(());
+        }
+        for (i, key) in self.key_list.key_list.iter().enumerate() {
+            if let Err(e) = key.validate() {
+                let path = format!("key_list.key_list[{}].flag", i);
+                return Err(PscError::InvalidValue {
+                    bit_offset: layout.find(&path).map_or(0, |s| s.bit_offset),
+                    path: format!("{}.{}", Self::NAME, path),
+                    message: e.to_string(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_key::entry::KeyEntry;
+    use crate::payload::tests::assert_walk_matches_parse;
+
+    #[test]
+    fn walk_matches_parse() {
+        for version in 0..=3 {
+            let mut key = GameKey {
+                version,
+                lanota_read_keys: [true, false, true, false, false, true],
+                camellia_read_key: (version >= 2).then_some(true),
+                side_story4_begin_read_key: (version >= 3).then_some(false),
+                old_score_cleared_v390: (version >= 3).then_some(true),
+                ..Default::default()
+            };
+            key.set("Glaciaxion", KeyEntry::song(true));
+            key.set("Introduction", KeyEntry::collection(2, 3));
+            key.set(
+                "Avatar",
+                KeyEntry {
+                    unlock_illustration: Some(true),
+                    unlock_avatar: Some(false),
+                    ..Default::default()
+                },
+            );
+            assert_walk_matches_parse(&key);
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::collections::BTreeMap;
 
+use super::entry::KeyEntry;
 use super::field::*;
 use crate::phi_base::*;
 use serde::{Deserialize, Serialize};
@@ -21,42 +22,37 @@ pub struct SerializableGameKey {
     pub camellia_read_key: Option<bool>,
     pub side_story4_begin_read_key: Option<bool>,
     pub old_score_cleared_v390: Option<bool>,
+    #[serde(default, skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
 }
 
-#[allow(unused_assignments)]
-impl From<Key> for SerializableKey {
-    fn from(k: Key) -> Self {
-        let mut flag_idx = 0;
-        let mut result = SerializableKey {
-            read_collection_piece_num: None,
-            unlock_single: None,
-            unlock_collection_piece_num: None,
-            unlock_illustration: None,
-            unlock_avatar: None,
-        };
-
-        if k.r#type.exist_read_collection_piece_num && flag_idx < k.flag.len() {
-            result.read_collection_piece_num = Some(k.flag[flag_idx]);
-            flag_idx += 1;
-        }
-        if k.r#type.exist_unlcok_single && flag_idx < k.flag.len() {
-            result.unlock_single = Some(k.flag[flag_idx] == 1);
-            flag_idx += 1;
-        }
-        if k.r#type.exist_unlock_collection_piece_num && flag_idx < k.flag.len() {
-            result.unlock_collection_piece_num = Some(k.flag[flag_idx]);
-            flag_idx += 1;
-        }
-        if k.r#type.exist_unlock_illustration && flag_idx < k.flag.len() {
-            result.unlock_illustration = Some(k.flag[flag_idx] == 1);
-            flag_idx += 1;
+impl From<KeyEntry> for SerializableKey {
+    fn from(e: KeyEntry) -> Self {
+        SerializableKey {
+            read_collection_piece_num: e.read_collection_piece_num,
+            unlock_single: e.unlock_single,
+            unlock_collection_piece_num: e.unlock_collection_piece_num,
+            unlock_illustration: e.unlock_illustration,
+            unlock_avatar: e.unlock_avatar,
         }
-        if k.r#type.exist_unlock_avatar && flag_idx < k.flag.len() {
-            result.unlock_avatar = Some(k.flag[flag_idx] == 1);
-            flag_idx += 1;
+    }
+}
+
+impl From<SerializableKey> for KeyEntry {
+    fn from(k: SerializableKey) -> Self {
+        KeyEntry {
+            read_collection_piece_num: k.read_collection_piece_num,
+            unlock_single: k.unlock_single,
+            unlock_collection_piece_num: k.unlock_collection_piece_num,
+            unlock_illustration: k.unlock_illustration,
+            unlock_avatar: k.unlock_avatar,
         }
+    }
+}
 
-        result
+impl From<Key> for SerializableKey {
+    fn from(k: Key) -> Self {
+        k.entry().into()
     }
 }
 
@@ -74,42 +70,14 @@ impl From<GameKey> for SerializableGameKey {
             camellia_read_key: gk.camellia_read_key,
             side_story4_begin_read_key: gk.side_story4_begin_read_key,
             old_score_cleared_v390: gk.old_score_cleared_v390,
+            extra: gk.extra,
         }
     }
 }
 
 impl From<(String, SerializableKey)> for Key {
     fn from((key_str, sk): (String, SerializableKey)) -> Self {
-        let mut flag = Vec::new();
-        let mut ktype = KeyType::default();
-
-        if let Some(v) = sk.read_collection_piece_num {
-            ktype.exist_read_collection_piece_num = true;
-            flag.push(v);
-        }
-        if let Some(v) = sk.unlock_single {
-            ktype.exist_unlcok_single = true;
-            flag.push(if v { 1 } else { 0 });
-        }
-        if let Some(v) = sk.unlock_collection_piece_num {
-            ktype.exist_unlock_collection_piece_num = true;
-            flag.push(v);
-        }
-        if let Some(v) = sk.unlock_illustration {
-            ktype.exist_unlock_illustration = true;
-            flag.push(if v { 1 } else { 0 });
-        }
-        if let Some(v) = sk.unlock_avatar {
-            ktype.exist_unlock_avatar = true;
-            flag.push(if v { 1 } else { 0 });
-        }
-
-        Key {
-            key: PhiString(key_str),
-            length: flag.len() as u8 + 1,
-            r#type: ktype,
-            flag,
-        }
+        Key::new(key_str, sk.into())
     }
 }
 
@@ -128,6 +96,7 @@ impl From<SerializableGameKey> for GameKey {
             camellia_read_key: sgk.camellia_read_key,
             side_story4_begin_read_key: sgk.side_story4_begin_read_key,
             old_score_cleared_v390: sgk.old_score_cleared_v390,
+            extra: sgk.extra,
         }
     }
 }
@@ -10,7 +10,7 @@ pub struct ProgressBase {
     pub already_show_auto_unlock_in_tip: bool,
 }
 
-#[derive(Debug, Default, BinaryField)]
+#[derive(Debug, Clone, Copy, Default, BinaryField)]
 #[binary_struct(bit_order = shua_struct::Lsb0)]
 pub struct Money {
     pub kib: VarInt,
@@ -53,6 +53,7 @@ pub struct GameProgress {
     pub chapter8_song_unlocked: [bool; 6],
     #[binary_field(align = 8, if_func = "is_version_at_least_4")]
     pub flag_of_song_record_key_takumi: Option<[bool; 3]>,
+    pub extra: Extra,
 }
 
 impl GameProgress {
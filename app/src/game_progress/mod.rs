@@ -1,4 +1,6 @@
 pub mod field;
+mod money;
+mod payload;
 
 #[cfg(feature = "serde")]
 pub mod serde;
@@ -0,0 +1,149 @@
+use super::field::Money;
+use crate::phi_base::{VARINT_MAX, VarInt};
+use std::fmt;
+
+const UNIT: u128 = 1024;
+static UNIT_NAMES: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+impl Money {
+    pub(crate) fn units(&self) -> [u16; 5] {
+        [self.kib.0, self.mib.0, self.gib.0, self.tib.0, self.pib.0]
+    }
+
+    pub fn to_bytes(&self) -> u128 {
+        self.units()
+            .iter()
+            .rev()
+            .fold(0, |acc, v| acc * UNIT + *v as u128)
+            * UNIT
+    }
+
+    // 游戏内最小单位为 KiB,不足 1 KiB 的部分舍去;超出 pib 的表示范围时返回 None
+    pub fn from_bytes(bytes: u128) -> Option<Self> {
+        let mut rest = bytes / UNIT;
+        let mut unit = || {
+            let v = (rest % UNIT) as u16;
+            rest /= UNIT;
+            VarInt(v)
+        };
+        let (kib, mib, gib, tib) = (unit(), unit(), unit(), unit());
+        if rest > VARINT_MAX as u128 {
+            return None;
+        }
+        Some(Money {
+            kib,
+            mib,
+            gib,
+            tib,
+            pib: VarInt(rest as u16),
+        })
+    }
+
+    // 除 pib 外每个单位都应小于 1024
+    pub fn is_normalized(&self) -> bool {
+        self.units()[..4].iter().all(|v| (*v as u128) < UNIT)
+    }
+
+    pub fn normalized(&self) -> Option<Self> {
+        Self::from_bytes(self.to_bytes())
+    }
+
+    pub fn checked_add(&self, rhs: &Money) -> Option<Self> {
+        Self::from_bytes(self.to_bytes().checked_add(rhs.to_bytes())?)
+    }
+
+    pub fn checked_sub(&self, rhs: &Money) -> Option<Self> {
+        Self::from_bytes(self.to_bytes().checked_sub(rhs.to_bytes())?)
+    }
+}
+
+// 取不小于 1 的最大单位,保留一位小数,如 "3.2 GiB"
+impl fmt::Display for Money {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let kib = self.to_bytes() / UNIT;
+        let mut scale = 1u128;
+        let mut i = 0;
+        while i + 1 < UNIT_NAMES.len() && kib >= scale * UNIT {
+            scale *= UNIT;
+            i += 1;
+        }
+
+        if kib.is_multiple_of(scale) {
+            write!(f, "{} {}", kib / scale, UNIT_NAMES[i])
+        } else {
+            write!(f, "{:.1} {}", kib as f64 / scale as f64, UNIT_NAMES[i])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn money(units: [u16; 5]) -> Money {
+        Money {
+            kib: VarInt(units[0]),
+            mib: VarInt(units[1]),
+            gib: VarInt(units[2]),
+            tib: VarInt(units[3]),
+            pib: VarInt(units[4]),
+        }
+    }
+
+    #[test]
+    fn add_carries_into_next_unit() {
+        let sum = money([0; 5])
+            .checked_add(&money([2000, 0, 0, 0, 0]))
+            .unwrap();
+        assert_eq!(sum.units(), [976, 1, 0, 0, 0]);
+        assert!(sum.is_normalized());
+
+        let sum = money([1023, 1023, 0, 0, 0])
+            .checked_add(&money([1, 0, 0, 0, 0]))
+            .unwrap();
+        assert_eq!(sum.units(), [0, 0, 1, 0, 0]);
+    }
+
+    #[test]
+    fn sub_borrows_and_rejects_negative() {
+        let diff = money([0, 0, 1, 0, 0])
+            .checked_sub(&money([1, 0, 0, 0, 0]))
+            .unwrap();
+        assert_eq!(diff.units(), [1023, 1023, 0, 0, 0]);
+        assert!(
+            money([1, 0, 0, 0, 0])
+                .checked_sub(&money([2, 0, 0, 0, 0]))
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn bytes_round_trip_and_overflow() {
+        let m = money([512, 3, 2, 1, 5]);
+        assert_eq!(Money::from_bytes(m.to_bytes()).unwrap().units(), m.units());
+        // 不足 1 KiB 的部分舍去
+        assert_eq!(
+            Money::from_bytes(1024 + 1023).unwrap().units(),
+            [1, 0, 0, 0, 0]
+        );
+        let max = money([1023, 1023, 1023, 1023, VARINT_MAX]);
+        assert!(max.checked_add(&money([1, 0, 0, 0, 0])).is_none());
+    }
+
+    #[test]
+    fn normalized_carries_oversized_units() {
+        let m = money([2000, 0, 0, 0, 0]);
+        assert!(!m.is_normalized());
+        assert_eq!(m.normalized().unwrap().units(), [976, 1, 0, 0, 0]);
+    }
+
+    #[test]
+    fn display_uses_largest_unit() {
+        assert_eq!(money([0; 5]).to_string(), "0 KiB");
+        assert_eq!(money([512, 0, 0, 0, 0]).to_string(), "512 KiB");
+        assert_eq!(money([0, 1, 0, 0, 0]).to_string(), "1 MiB");
+        assert_eq!(money([976, 1, 0, 0, 0]).to_string(), "2.0 MiB");
+        assert_eq!(money([0, 205, 3, 0, 0]).to_string(), "3.2 GiB");
+        assert_eq!(money([0, 0, 0, 0, 2]).to_string(), "2 PiB");
+    }
+}
@@ -0,0 +1,112 @@
+use super::field::{Chapter8Base, GameProgress, ProgressBase};
+use crate::error::PscError;
+use crate::layout::FieldSpan;
+use crate::payload::{ParseOptions, Payload, Walker};
+use crate::phi_base::Extra;
+
+impl Payload for GameProgress {
+    const NAME: &'static str = "game_progress";
+    const MAX_VERSION: Option<u8> = Some(4);
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn extra(&self) -> &Extra {
+        &self.extra
+    }
+
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError> {
+        let version = w.read::<u8>("version")?;
+        w.read_aligned::<ProgressBase>("base", 8)?;
+        w.string("completed")?;
+        w.varint("song_update_info")?;
+        w.read::<u16>("challenge_mode_rank")?;
+        w.nest("money", |w| {
+            for unit in ["kib", "mib", "gib", "tib", "pib"] {
+                w.varint(unit)?;
+            }
+            Ok(())
+        })?;
+        w.read_aligned::<[bool; 4]>("unlock_flag_of_spasmodic", 8)?;
+        w.read_aligned::<[bool; 4]>("unlock_flag_of_igallta", 8)?;
+        w.read_aligned::<[bool; 4]>("unlock_flag_of_rrharil", 8)?;
+        w.read::<[bool; 8]>("flag_of_song_record_key")?;
+        if version >= 2 {
+            w.read_aligned::<[bool; 6]>("random_version_unlocked", 8)?;
+        }
+        if version >= 3 {
+            w.read_aligned::<Chapter8Base>("chapter8_base", 8)?;
+            w.read_aligned::<[bool; 6]>("chapter8_song_unlocked", 8)?;
+        }
+        if version >= 4 {
+            w.read_aligned::<[bool; 3]>("flag_of_song_record_key_takumi", 8)?;
+        }
+        w.rest("extra");
+        Ok(())
+    }
+
+    fn check(&self, layout: &FieldSpan, options: &ParseOptions) -> Result<(), PscError> {
+        if !(options.strict || options.reject_unnormalized_money) || self.money.is_normalized() {
+            return Ok(());
+        }
+        Err(PscError::InvalidValue {
+            path: format!("{}.money", Self::NAME),
+            bit_offset: layout.find("money").map_or(0, |s| s.bit_offset),
+            message: format!("数据未进位: {:?}", self.money),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::game_progress::field::Money;
+    use crate::payload::tests::assert_walk_matches_parse;
+    use crate::phi_base::{PhiString, VarInt};
+
+    #[test]
+    fn walk_matches_parse() {
+        for version in 0..=4 {
+            let progress = GameProgress {
+                version,
+                completed: PhiString::from("3.0"),
+                song_update_info: VarInt(300),
+                challenge_mode_rank: 348,
+                money: Money {
+                    kib: VarInt(1000),
+                    mib: VarInt(3),
+                    ..Default::default()
+                },
+                unlock_flag_of_igallta: [true, true, false, false],
+                flag_of_song_record_key: [true; 8],
+                flag_of_song_record_key_takumi: (version >= 4).then_some([true, false, true]),
+                ..Default::default()
+            };
+            assert_walk_matches_parse(&progress);
+        }
+    }
+
+    // 未进位的 Money 可以单独检查,不必开启 strict
+    #[test]
+    fn unnormalized_money_is_rejected_on_request() {
+        let progress = GameProgress {
+            money: Money {
+                kib: VarInt(2000),
+                ..Default::default()
+            },
+            extra: Extra(vec![0]),
+            ..Default::default()
+        };
+        let bytes = progress.build_bytes().unwrap();
+
+        assert!(GameProgress::parse_with(&bytes, &ParseOptions::default()).is_ok());
+        let options = ParseOptions {
+            reject_unnormalized_money: true,
+            ..Default::default()
+        };
+        let err = GameProgress::parse_with(&bytes, &options).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidValue);
+    }
+}
@@ -1,4 +1,5 @@
 use super::field::{Chapter8Base, GameProgress, Money, ProgressBase};
+use crate::challenge::ChallengeRank;
 use crate::phi_base::*;
 use serde::{Deserialize, Serialize};
 
@@ -33,6 +34,9 @@ pub struct SerializableGameProgress {
     pub completed: String,
     pub song_update_info: u16,
     pub challenge_mode_rank: u16,
+    // 由 challenge_mode_rank 解码,仅用于输出,构建时以原始值为准
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub challenge_rank: Option<ChallengeRank>,
     pub money: SerializableMoney,
     pub unlock_flag_of_spasmodic: [bool; 4],
     pub unlock_flag_of_igallta: [bool; 4],
@@ -42,6 +46,8 @@ pub struct SerializableGameProgress {
     pub chapter8_base: SerializableChapter8Base,
     pub chapter8_song_unlocked: [bool; 6],
     pub flag_of_song_record_key_takumi: Option<[bool; 3]>,
+    #[serde(default, skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
 }
 
 impl From<ProgressBase> for SerializableBase {
@@ -118,6 +124,9 @@ impl From<GameProgress> for SerializableGameProgress {
             completed: g.completed.0,
             song_update_info: g.song_update_info.0,
             challenge_mode_rank: g.challenge_mode_rank,
+            challenge_rank: ChallengeRank::from_raw(g.challenge_mode_rank)
+                .ok()
+                .flatten(),
             money: g.money.into(),
             unlock_flag_of_spasmodic: g.unlock_flag_of_spasmodic,
             unlock_flag_of_igallta: g.unlock_flag_of_igallta,
@@ -127,6 +136,7 @@ impl From<GameProgress> for SerializableGameProgress {
             chapter8_base: g.chapter8_base.into(),
             chapter8_song_unlocked: g.chapter8_song_unlocked,
             flag_of_song_record_key_takumi: g.flag_of_song_record_key_takumi,
+            extra: g.extra,
         }
     }
 }
@@ -148,6 +158,7 @@ impl From<SerializableGameProgress> for GameProgress {
             chapter8_base: g.chapter8_base.into(),
             chapter8_song_unlocked: g.chapter8_song_unlocked,
             flag_of_song_record_key_takumi: g.flag_of_song_record_key_takumi,
+            extra: g.extra,
         }
     }
 }
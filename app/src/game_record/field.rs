@@ -1,6 +1,8 @@
 use crate::phi_base::*;
 use shua_struct::BinaryField;
 
+pub static DIFF_ORDER: [&str; 5] = ["EZ", "HD", "IN", "AT", "Legacy"];
+
 #[derive(Debug, Default, BinaryField)]
 #[binary_struct(bit_order = shua_struct::Lsb0)]
 pub struct LevelRecord {
@@ -33,4 +35,5 @@ pub struct GameRecord {
     pub song_sum: VarInt,
     #[binary_field(count_field = song_sum)]
     pub song_list: Vec<SongEntry>,
+    pub extra: Extra,
 }
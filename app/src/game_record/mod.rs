@@ -1,4 +1,6 @@
 pub mod field;
+mod payload;
+pub mod song;
 
 #[cfg(feature = "serde")]
 pub mod serde;
@@ -0,0 +1,63 @@
+use super::field::GameRecord;
+use crate::error::PscError;
+use crate::payload::{Payload, Walker};
+use crate::phi_base::Extra;
+
+impl Payload for GameRecord {
+    const NAME: &'static str = "game_record";
+    const MAX_VERSION: Option<u8> = Some(1);
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn extra(&self) -> &Extra {
+        &self.extra
+    }
+
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError> {
+        w.read::<u8>("version")?;
+        let song_sum = w.varint("song_sum")?;
+        w.list("song_list", song_sum as usize, |w| {
+            w.string("name")?;
+            w.varint("length")?;
+            let unlock = w.read_aligned::<[bool; 5]>("unlock", 8)?;
+            w.read_aligned::<[bool; 5]>("fc", 8)?;
+            let levels = unlock.iter().filter(|b| **b).count();
+            w.list("levels", levels, |w| {
+                w.read::<u32>("score")?;
+                w.read::<f32>("acc")?;
+                Ok(())
+            })
+        })?;
+        w.rest("extra");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_record::song::{ChartRecord, Difficulty};
+    use crate::payload::tests::assert_walk_matches_parse;
+
+    #[test]
+    fn walk_matches_parse() {
+        let mut record = GameRecord {
+            version: 1,
+            ..Default::default()
+        };
+        assert_walk_matches_parse(&record);
+
+        let chart = |score| ChartRecord {
+            score,
+            acc: 98.5,
+            fc: true,
+        };
+        record.set("Glaciaxion.SunsetRay.0", Difficulty::EZ, chart(1000000));
+        record.set("Glaciaxion.SunsetRay.0", Difficulty::AT, chart(987654));
+        // 超过 127 字节的曲名,长度占两个字节
+        record.set("a".repeat(200), Difficulty::Legacy, chart(900000));
+        assert_walk_matches_parse(&record);
+    }
+}
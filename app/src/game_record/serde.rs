@@ -1,80 +1,140 @@
-use super::field::{GameRecord, LevelRecord, SongEntry};
+use super::field::{GameRecord, SongEntry};
+use super::song::{ChartRecord, Difficulty};
+use crate::grade::{ClearState, Grade};
 use crate::phi_base::*;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::BTreeMap;
 
-static DIFF_ORDER: [&str; 5] = ["EZ", "HD", "IN", "AT", "Legacy"];
-
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SerializableLevelRecord {
     pub score: u32,
     pub acc: f32,
     pub fc: bool,
+    // 由 score/fc 推导,仅在 with_grades 后输出,构建时忽略
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub grade: Option<Grade>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub clear_state: Option<ClearState>,
 }
-pub type SerializableSongRecord = BTreeMap<String, SerializableLevelRecord>;
+pub type SerializableSongRecord = BTreeMap<Difficulty, SerializableLevelRecord>;
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SerializableGameRecord {
     pub version: u8,
     pub songs: BTreeMap<String, SerializableSongRecord>,
+    #[serde(default, skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
+}
+
+impl SerializableGameRecord {
+    pub fn with_grades(mut self) -> Self {
+        for level in self.songs.values_mut().flat_map(|s| s.values_mut()) {
+            let chart = ChartRecord {
+                score: level.score,
+                acc: level.acc,
+                fc: level.fc,
+            };
+            level.grade = Some(chart.grade());
+            level.clear_state = Some(chart.clear_state());
+        }
+        self
+    }
+}
+
+// 未知的难度名直接报错,避免拼写错误的难度被静默丢弃
+impl Serialize for Difficulty {
+    fn serialize<S: Serializer>(&self, s: S) -> Result<S::Ok, S::Error> {
+        s.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for Difficulty {
+    fn deserialize<D: Deserializer<'de>>(d: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(d)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
 }
 
 impl From<GameRecord> for SerializableGameRecord {
     fn from(gr: GameRecord) -> Self {
         let mut map: BTreeMap<String, SerializableSongRecord> = BTreeMap::new();
         for song in gr.song_list {
-            let mut song_map: BTreeMap<String, SerializableLevelRecord> = BTreeMap::new();
-            let mut level_idx = 0;
-            for (i, diff) in DIFF_ORDER.iter().enumerate() {
-                if song.unlock[i] {
-                    let level = &song.levels[level_idx];
-                    song_map.insert(
-                        diff.to_string(),
-                        SerializableLevelRecord {
-                            score: level.score,
-                            acc: level.acc,
-                            fc: song.fc[i],
-                        },
-                    );
-                    level_idx += 1;
-                }
-            }
+            let song_map = song
+                .iter_levels()
+                .map(|(diff, r)| {
+                    let level = SerializableLevelRecord {
+                        score: r.score,
+                        acc: r.acc,
+                        fc: r.fc,
+                        grade: None,
+                        clear_state: None,
+                    };
+                    (diff, level)
+                })
+                .collect();
             map.insert(song.name.0, song_map);
         }
         SerializableGameRecord {
             version: gr.version,
             songs: map,
+            extra: gr.extra,
         }
     }
 }
+
 impl From<SerializableGameRecord> for GameRecord {
     fn from(sgr: SerializableGameRecord) -> Self {
         let mut song_list: Vec<SongEntry> = Vec::new();
         for (name, song_map) in sgr.songs {
-            let mut unlock = [false; 5];
-            let mut fc = [false; 5];
-            let mut levels: Vec<LevelRecord> = Vec::new();
-            for (i, diff) in DIFF_ORDER.iter().enumerate() {
-                if let Some(rec) = song_map.get(*diff) {
-                    unlock[i] = true;
-                    fc[i] = rec.fc;
-                    levels.push(LevelRecord {
-                        score: rec.score,
-                        acc: rec.acc,
-                    });
-                }
-            }
-            song_list.push(SongEntry {
+            let mut song = SongEntry {
                 name: PhiString(name),
-                length: VarInt((levels.len() as u16) * 8 + 2),
-                unlock,
-                fc,
-                levels,
-            });
+                length: VarInt(2),
+                ..Default::default()
+            };
+            for (diff, rec) in song_map {
+                let chart = ChartRecord {
+                    score: rec.score,
+                    acc: rec.acc,
+                    fc: rec.fc,
+                };
+                song.set(diff, chart);
+            }
+            song_list.push(song);
         }
         GameRecord {
             version: sgr.version,
             song_sum: VarInt(song_list.len() as u16),
             song_list,
+            extra: sgr.extra,
         }
     }
 }
+
+#[cfg(all(test, feature = "json"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_unknown_difficulty() {
+        let json =
+            r#"{"version": 1, "songs": {"A.X.0": {"SP": {"score": 1, "acc": 1.0, "fc": false}}}}"#;
+        let err = serde_json::from_str::<SerializableGameRecord>(json).unwrap_err();
+        assert!(err.to_string().contains("未知难度: SP"), "{}", err);
+    }
+
+    #[test]
+    fn sparse_difficulties_round_trip() {
+        let json = r#"{"version": 1, "songs": {"A.X.0": {
+            "AT": {"score": 4, "acc": 4.0, "fc": true},
+            "EZ": {"score": 1, "acc": 1.0, "fc": false}}}}"#;
+        let record =
+            GameRecord::from(serde_json::from_str::<SerializableGameRecord>(json).unwrap());
+        let song = &record.song_list[0];
+        assert_eq!(song.unlock, [true, false, false, true, false]);
+        assert_eq!(song.levels[1].score, 4);
+        assert_eq!(song.get(Difficulty::AT).map(|r| r.fc), Some(true));
+
+        let back = SerializableGameRecord::from(record);
+        let levels: Vec<Difficulty> = back.songs["A.X.0"].keys().copied().collect();
+        assert_eq!(levels, [Difficulty::EZ, Difficulty::AT]);
+    }
+}
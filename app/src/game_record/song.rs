@@ -0,0 +1,342 @@
+use super::field::{DIFF_ORDER, GameRecord, LevelRecord, SongEntry};
+use crate::phi_base::{PhiString, VarInt};
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Difficulty {
+    EZ,
+    HD,
+    IN,
+    AT,
+    Legacy,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownDifficulty(pub String);
+
+impl fmt::Display for UnknownDifficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "未知难度: {}", self.0)
+    }
+}
+
+impl std::error::Error for UnknownDifficulty {}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 5] = [
+        Difficulty::EZ,
+        Difficulty::HD,
+        Difficulty::IN,
+        Difficulty::AT,
+        Difficulty::Legacy,
+    ];
+
+    // 在 unlock/fc 位数组中的下标
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn from_index(i: usize) -> Option<Self> {
+        Self::ALL.get(i).copied()
+    }
+
+    pub fn as_str(self) -> &'static str {
+        DIFF_ORDER[self.index()]
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for Difficulty {
+    type Err = UnknownDifficulty;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        DIFF_ORDER
+            .iter()
+            .position(|d| *d == s)
+            .and_then(Self::from_index)
+            .ok_or_else(|| UnknownDifficulty(s.to_string()))
+    }
+}
+
+// 曲目 id 形如 "Glaciaxion.SunsetRay.0": 曲名.曲师.序号,曲名中可能含有 '.'
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct SongId(String);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidSongId(pub String);
+
+impl fmt::Display for InvalidSongId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "无效的曲目 id: {}", self.0)
+    }
+}
+
+impl std::error::Error for InvalidSongId {}
+
+impl SongId {
+    pub fn new(name: &str, artist: &str, index: u32) -> Self {
+        SongId(format!("{}.{}.{}", name, artist, index))
+    }
+
+    fn parts(&self) -> (&str, &str, u32) {
+        let mut it = self.0.rsplitn(3, '.');
+        let index = it.next().and_then(|s| s.parse().ok()).unwrap_or_default();
+        let artist = it.next().unwrap_or_default();
+        let name = it.next().unwrap_or_default();
+        (name, artist, index)
+    }
+
+    pub fn name(&self) -> &str {
+        self.parts().0
+    }
+
+    pub fn artist(&self) -> &str {
+        self.parts().1
+    }
+
+    pub fn index(&self) -> u32 {
+        self.parts().2
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl FromStr for SongId {
+    type Err = InvalidSongId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut it = s.rsplitn(3, '.');
+        let index_ok = it.next().is_some_and(|i| i.parse::<u32>().is_ok());
+        let artist_ok = it.next().is_some_and(|a| !a.is_empty());
+        let name_ok = it.next().is_some_and(|n| !n.is_empty());
+        if index_ok && artist_ok && name_ok {
+            Ok(SongId(s.to_string()))
+        } else {
+            Err(InvalidSongId(s.to_string()))
+        }
+    }
+}
+
+impl fmt::Display for SongId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl AsRef<str> for SongId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ChartRecord {
+    pub score: u32,
+    pub acc: f32,
+    pub fc: bool,
+}
+
+impl SongEntry {
+    // levels 只存放已解锁的难度,按难度顺序排列
+    fn level_slot(&self, diff: Difficulty) -> usize {
+        self.unlock[..diff.index()].iter().filter(|u| **u).count()
+    }
+
+    pub fn get(&self, diff: Difficulty) -> Option<ChartRecord> {
+        if !self.unlock[diff.index()] {
+            return None;
+        }
+        let level = self.levels.get(self.level_slot(diff))?;
+        Some(ChartRecord {
+            score: level.score,
+            acc: level.acc,
+            fc: self.fc[diff.index()],
+        })
+    }
+
+    pub fn set(&mut self, diff: Difficulty, record: ChartRecord) {
+        let slot = self.level_slot(diff);
+        let level = LevelRecord {
+            score: record.score,
+            acc: record.acc,
+        };
+        if self.unlock[diff.index()] {
+            self.levels[slot] = level;
+        } else {
+            self.levels.insert(slot, level);
+            self.unlock[diff.index()] = true;
+        }
+        self.fc[diff.index()] = record.fc;
+        self.update_length();
+    }
+
+    pub fn remove(&mut self, diff: Difficulty) -> Option<ChartRecord> {
+        let record = self.get(diff)?;
+        self.levels.remove(self.level_slot(diff));
+        self.unlock[diff.index()] = false;
+        self.fc[diff.index()] = false;
+        self.update_length();
+        Some(record)
+    }
+
+    pub fn iter_levels(&self) -> impl Iterator<Item = (Difficulty, ChartRecord)> + '_ {
+        Difficulty::ALL
+            .into_iter()
+            .filter_map(|d| self.get(d).map(|r| (d, r)))
+    }
+
+    // unlock 与 fc 各占 1 字节,每个难度 8 字节
+    fn update_length(&mut self) {
+        self.length = VarInt((self.levels.len() as u16) * 8 + 2);
+    }
+}
+
+impl GameRecord {
+    pub fn song(&self, song: &str) -> Option<&SongEntry> {
+        self.song_list.iter().find(|s| s.name.0 == song)
+    }
+
+    pub fn get(&self, song: impl AsRef<str>, diff: Difficulty) -> Option<ChartRecord> {
+        self.song(song.as_ref())?.get(diff)
+    }
+
+    // 曲目不存在时追加到末尾
+    pub fn set(&mut self, song: impl AsRef<str>, diff: Difficulty, record: ChartRecord) {
+        let song = song.as_ref();
+        let entry = match self.song_list.iter().position(|s| s.name.0 == song) {
+            Some(i) => &mut self.song_list[i],
+            None => {
+                self.song_list.push(SongEntry {
+                    name: PhiString::from(song),
+                    ..Default::default()
+                });
+                self.song_sum = VarInt(self.song_list.len() as u16);
+                self.song_list.last_mut().unwrap()
+            }
+        };
+        entry.set(diff, record);
+    }
+
+    // 曲目的最后一个难度被移除时一并移除曲目
+    pub fn remove(&mut self, song: impl AsRef<str>, diff: Difficulty) -> Option<ChartRecord> {
+        let song = song.as_ref();
+        let i = self.song_list.iter().position(|s| s.name.0 == song)?;
+        let record = self.song_list[i].remove(diff)?;
+        if self.song_list[i].levels.is_empty() {
+            self.song_list.remove(i);
+            self.song_sum = VarInt(self.song_list.len() as u16);
+        }
+        Some(record)
+    }
+
+    pub fn iter_levels(&self) -> impl Iterator<Item = (&str, Difficulty, ChartRecord)> + '_ {
+        self.song_list
+            .iter()
+            .flat_map(|s| s.iter_levels().map(move |(d, r)| (s.name.0.as_str(), d, r)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(score: u32) -> ChartRecord {
+        ChartRecord {
+            score,
+            acc: 90.0,
+            fc: false,
+        }
+    }
+
+    #[test]
+    fn song_id_with_dots_in_name() {
+        let id: SongId = "Dr.Feelgood.Hiroyuki Oshima.0".parse().unwrap();
+        assert_eq!(id.name(), "Dr.Feelgood");
+        assert_eq!(id.artist(), "Hiroyuki Oshima");
+        assert_eq!(id.index(), 0);
+        assert_eq!(SongId::new("Dr.Feelgood", "Hiroyuki Oshima", 0), id);
+
+        let id: SongId = "Glaciaxion.SunsetRay.12".parse().unwrap();
+        assert_eq!(
+            (id.name(), id.artist(), id.index()),
+            ("Glaciaxion", "SunsetRay", 12)
+        );
+
+        for bad in [
+            "Glaciaxion.SunsetRay",
+            "Glaciaxion.SunsetRay.x",
+            ".SunsetRay.0",
+            "A..0",
+        ] {
+            assert_eq!(bad.parse::<SongId>(), Err(InvalidSongId(bad.to_string())));
+        }
+    }
+
+    #[test]
+    fn difficulty_names() {
+        for d in Difficulty::ALL {
+            assert_eq!(d.as_str().parse(), Ok(d));
+        }
+        assert_eq!(
+            "ez".parse::<Difficulty>(),
+            Err(UnknownDifficulty("ez".to_string()))
+        );
+    }
+
+    #[test]
+    fn set_and_remove_keep_levels_in_difficulty_order() {
+        let mut song = SongEntry::default();
+        song.set(Difficulty::AT, chart(4));
+        song.set(Difficulty::EZ, chart(1));
+        song.set(Difficulty::IN, chart(3));
+        assert_eq!(song.unlock, [true, false, true, true, false]);
+        let scores: Vec<u32> = song.levels.iter().map(|l| l.score).collect();
+        assert_eq!(scores, [1, 3, 4]);
+        assert_eq!(song.length.0, 3 * 8 + 2);
+
+        // 覆盖已有难度不改变槽位
+        song.set(Difficulty::IN, chart(30));
+        assert_eq!(song.get(Difficulty::IN), Some(chart(30)));
+        assert_eq!(song.get(Difficulty::AT), Some(chart(4)));
+        assert_eq!(song.get(Difficulty::HD), None);
+
+        assert_eq!(song.remove(Difficulty::EZ), Some(chart(1)));
+        assert_eq!(song.remove(Difficulty::EZ), None);
+        assert_eq!(song.get(Difficulty::IN), Some(chart(30)));
+        assert_eq!(song.get(Difficulty::AT), Some(chart(4)));
+        assert_eq!(song.length.0, 2 * 8 + 2);
+
+        song.set(Difficulty::HD, chart(2));
+        let levels: Vec<(Difficulty, u32)> =
+            song.iter_levels().map(|(d, r)| (d, r.score)).collect();
+        assert_eq!(
+            levels,
+            [
+                (Difficulty::HD, 2),
+                (Difficulty::IN, 30),
+                (Difficulty::AT, 4)
+            ]
+        );
+    }
+
+    #[test]
+    fn record_removes_song_with_its_last_level() {
+        let mut record = GameRecord::default();
+        record.set("A.X.0", Difficulty::IN, chart(1));
+        record.set("B.Y.0", Difficulty::HD, chart(2));
+        assert_eq!(record.song_sum.0, 2);
+
+        assert_eq!(record.remove("A.X.0", Difficulty::IN), Some(chart(1)));
+        assert!(record.song("A.X.0").is_none());
+        assert_eq!(record.song_sum.0, 1);
+        assert_eq!(record.remove("A.X.0", Difficulty::IN), None);
+    }
+}
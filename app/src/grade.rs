@@ -0,0 +1,254 @@
+use crate::game_record::field::GameRecord;
+use crate::game_record::song::{ChartRecord, Difficulty};
+use crate::rks::PHI_SCORE;
+use crate::summary::field::{Level, Summary};
+use std::fmt;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+pub const CLEAR_SCORE: u32 = 700_000;
+
+// 评级下限,从高到低
+static GRADE_THRESHOLDS: [(u32, Grade); 6] = [
+    (PHI_SCORE, Grade::Phi),
+    (960_000, Grade::V),
+    (920_000, Grade::S),
+    (880_000, Grade::A),
+    (820_000, Grade::B),
+    (CLEAR_SCORE, Grade::C),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Grade {
+    F,
+    C,
+    B,
+    A,
+    S,
+    V,
+    #[cfg_attr(feature = "serde", serde(rename = "φ"))]
+    Phi,
+}
+
+impl Grade {
+    pub fn from_score(score: u32) -> Self {
+        GRADE_THRESHOLDS
+            .iter()
+            .find(|(min, _)| score >= *min)
+            .map_or(Grade::F, |(_, g)| *g)
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Grade::Phi => "φ",
+            Grade::V => "V",
+            Grade::S => "S",
+            Grade::A => "A",
+            Grade::B => "B",
+            Grade::C => "C",
+            Grade::F => "F",
+        }
+    }
+}
+
+impl fmt::Display for Grade {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum ClearState {
+    Failed,
+    Cleared,
+    FullCombo,
+    AllPerfect,
+}
+
+impl ChartRecord {
+    pub fn grade(&self) -> Grade {
+        Grade::from_score(self.score)
+    }
+
+    pub fn clear_state(&self) -> ClearState {
+        if self.score >= PHI_SCORE {
+            ClearState::AllPerfect
+        } else if self.fc {
+            ClearState::FullCombo
+        } else if self.score >= CLEAR_SCORE {
+            ClearState::Cleared
+        } else {
+            ClearState::Failed
+        }
+    }
+}
+
+// 与 Summary 中的计数口径一致: φ 也计入 fc,fc 也计入 clear
+pub fn level_counts(record: &GameRecord, diff: Difficulty) -> Level {
+    let mut level = Level::default();
+    for (_, d, chart) in record.iter_levels() {
+        if d != diff {
+            continue;
+        }
+        let state = chart.clear_state();
+        if state >= ClearState::Cleared {
+            level.clear += 1;
+        }
+        if state >= ClearState::FullCombo {
+            level.fc += 1;
+        }
+        if state == ClearState::AllPerfect {
+            level.phi += 1;
+        }
+    }
+    level
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct LevelCountMismatch {
+    pub difficulty: Difficulty,
+    pub field: &'static str,
+    pub summary: u16,
+    pub record: u16,
+}
+
+impl Summary {
+    pub fn level(&self, diff: Difficulty) -> Option<&Level> {
+        match diff {
+            Difficulty::EZ => Some(&self.level.ez),
+            Difficulty::HD => Some(&self.level.hd),
+            Difficulty::IN => Some(&self.level.r#in),
+            Difficulty::AT => Some(&self.level.at),
+            Difficulty::Legacy => None,
+        }
+    }
+
+    // Summary 不统计 Legacy 谱面
+    pub fn check_level_counts(&self, record: &GameRecord) -> Vec<LevelCountMismatch> {
+        let mut mismatches = Vec::new();
+        for diff in Difficulty::ALL {
+            let Some(summary) = self.level(diff) else {
+                continue;
+            };
+            let counted = level_counts(record, diff);
+            for (field, s, r) in [
+                ("clear", summary.clear, counted.clear),
+                ("fc", summary.fc, counted.fc),
+                ("phi", summary.phi, counted.phi),
+            ] {
+                if s != r {
+                    mismatches.push(LevelCountMismatch {
+                        difficulty: diff,
+                        field,
+                        summary: s,
+                        record: r,
+                    });
+                }
+            }
+        }
+        mismatches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chart(score: u32, fc: bool) -> ChartRecord {
+        ChartRecord {
+            score,
+            acc: 0.0,
+            fc,
+        }
+    }
+
+    #[test]
+    fn grade_thresholds() {
+        for (below, at, grade) in [
+            (699_999, 700_000, Grade::C),
+            (819_999, 820_000, Grade::B),
+            (879_999, 880_000, Grade::A),
+            (919_999, 920_000, Grade::S),
+            (959_999, 960_000, Grade::V),
+            (999_999, 1_000_000, Grade::Phi),
+        ] {
+            assert!(Grade::from_score(below) < grade, "{}", below);
+            assert_eq!(Grade::from_score(at), grade, "{}", at);
+        }
+        assert_eq!(Grade::from_score(0), Grade::F);
+        assert_eq!(Grade::from_score(699_999), Grade::F);
+        assert_eq!(Grade::Phi.to_string(), "φ");
+    }
+
+    #[test]
+    fn clear_state() {
+        assert_eq!(chart(699_999, false).clear_state(), ClearState::Failed);
+        assert_eq!(chart(700_000, false).clear_state(), ClearState::Cleared);
+        // fc 标记优先于分数,低于 70 万的 fc 同样算作 FullCombo
+        assert_eq!(chart(650_000, true).clear_state(), ClearState::FullCombo);
+        assert_eq!(chart(990_000, true).clear_state(), ClearState::FullCombo);
+        assert_eq!(
+            chart(1_000_000, false).clear_state(),
+            ClearState::AllPerfect
+        );
+        assert_eq!(chart(1_000_000, true).clear_state(), ClearState::AllPerfect);
+    }
+
+    fn record() -> GameRecord {
+        let mut record = GameRecord::default();
+        record.set("A.X.0", Difficulty::EZ, chart(1_000_000, true));
+        record.set("A.X.0", Difficulty::HD, chart(960_000, true));
+        record.set("A.X.0", Difficulty::IN, chart(800_000, false));
+        record.set("B.Y.0", Difficulty::HD, chart(700_000, false));
+        record.set("B.Y.0", Difficulty::IN, chart(500_000, false));
+        record.set("B.Y.0", Difficulty::Legacy, chart(1_000_000, true));
+        record
+    }
+
+    #[test]
+    fn counts_levels_per_difficulty() {
+        let counts = |d| {
+            let l = level_counts(&record(), d);
+            (l.clear, l.fc, l.phi)
+        };
+        assert_eq!(counts(Difficulty::EZ), (1, 1, 1));
+        assert_eq!(counts(Difficulty::HD), (2, 1, 0));
+        assert_eq!(counts(Difficulty::IN), (1, 0, 0));
+        assert_eq!(counts(Difficulty::AT), (0, 0, 0));
+        assert_eq!(counts(Difficulty::Legacy), (1, 1, 1));
+    }
+
+    #[test]
+    fn check_level_counts_reports_mismatches() {
+        let mut summary = Summary::default();
+        summary.level.ez = level_counts(&record(), Difficulty::EZ);
+        summary.level.hd = level_counts(&record(), Difficulty::HD);
+        summary.level.r#in = level_counts(&record(), Difficulty::IN);
+        assert!(summary.check_level_counts(&record()).is_empty());
+
+        summary.level.hd.fc = 2;
+        summary.level.at.phi = 1;
+        assert_eq!(
+            summary.check_level_counts(&record()),
+            vec![
+                LevelCountMismatch {
+                    difficulty: Difficulty::HD,
+                    field: "fc",
+                    summary: 2,
+                    record: 1,
+                },
+                LevelCountMismatch {
+                    difficulty: Difficulty::AT,
+                    field: "phi",
+                    summary: 1,
+                    record: 0,
+                },
+            ]
+        );
+    }
+}
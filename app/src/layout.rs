@@ -0,0 +1,152 @@
+use crate::error::PscError;
+use crate::payload::{Payload, Walker};
+use std::fmt::Write;
+
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
+
+const BYTES_PER_LINE: usize = 16;
+const FIELD_WIDTH: usize = 44;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct FieldSpan {
+    pub name: String,
+    pub bit_offset: usize,
+    pub bit_len: usize,
+    // 对齐跳过的位数,紧跟在字段数据之后
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "is_zero"))]
+    pub padding_bits: usize,
+    // 只有叶子字段带原始字节,按字节边界截取
+    #[cfg_attr(
+        feature = "serde",
+        serde(serialize_with = "hex_bytes", skip_serializing_if = "Vec::is_empty")
+    )]
+    pub bytes: Vec<u8>,
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Vec::is_empty"))]
+    pub children: Vec<FieldSpan>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct Layout {
+    pub root: FieldSpan,
+    // 数据无法完整解析时,root 只包含出错之前的字段
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            serialize_with = "display_error",
+            skip_serializing_if = "Option::is_none"
+        )
+    )]
+    pub error: Option<PscError>,
+}
+
+#[cfg(feature = "serde")]
+fn is_zero(v: &usize) -> bool {
+    *v == 0
+}
+
+#[cfg(feature = "serde")]
+fn hex_bytes<S: Serializer>(bytes: &[u8], s: S) -> Result<S::Ok, S::Error> {
+    s.serialize_str(&hex(bytes))
+}
+
+#[cfg(feature = "serde")]
+fn display_error<S: Serializer>(e: &Option<PscError>, s: S) -> Result<S::Ok, S::Error> {
+    match e {
+        Some(e) => s.serialize_str(&e.to_string()),
+        None => s.serialize_none(),
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+impl FieldSpan {
+    pub(crate) fn group(name: impl Into<String>, bit_offset: usize) -> Self {
+        FieldSpan {
+            name: name.into(),
+            bit_offset,
+            ..Default::default()
+        }
+    }
+
+    // 相对当前节点的路径,如 "key_list.key_list[5].flag"
+    pub fn find(&self, path: &str) -> Option<&FieldSpan> {
+        let path = path.replace('[', ".[");
+        path.split('.')
+            .filter(|s| !s.is_empty())
+            .try_fold(self, |node, name| {
+                node.children.iter().find(|c| c.name == name)
+            })
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut out = String::new();
+        self.write_text(&mut out, 0);
+        out
+    }
+
+    // 偏移以 字节.位 表示,位长不含对齐填充
+    fn write_text(&self, out: &mut String, depth: usize) {
+        let mut field = format!("{}{}", "  ".repeat(depth), self.name);
+        if self.padding_bits > 0 {
+            let _ = write!(field, " (+{} 位填充)", self.padding_bits);
+        }
+
+        // 中文字符按两列宽度对齐
+        let width = field
+            .chars()
+            .map(|c| if c.is_ascii() { 1 } else { 2 })
+            .sum::<usize>();
+        field.push_str(&" ".repeat(FIELD_WIDTH.saturating_sub(width)));
+
+        let mut chunks = self.bytes.chunks(BYTES_PER_LINE);
+        let _ = writeln!(
+            out,
+            "{:08x}.{}  {:>6}  {}  {}",
+            self.bit_offset / 8,
+            self.bit_offset % 8,
+            self.bit_len,
+            field,
+            chunks.next().map(hex).unwrap_or_default(),
+        );
+        for chunk in chunks {
+            let _ = writeln!(out, "{:<w$}  {}", "", hex(chunk), w = FIELD_WIDTH + 20);
+        }
+
+        for child in &self.children {
+            child.write_text(out, depth + 1);
+        }
+    }
+}
+
+impl Layout {
+    pub fn to_text(&self) -> String {
+        let mut out = self.root.to_text();
+        if let Some(e) = &self.error {
+            let _ = writeln!(out, "错误: {}", e);
+        }
+        out
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
+
+pub fn layout<T: Payload>(bytes: &[u8]) -> Layout {
+    let mut w = Walker::new(T::NAME, bytes);
+    let error = T::walk(&mut w).err();
+    Layout {
+        root: w.into_layout(),
+        error,
+    }
+}
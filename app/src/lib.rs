@@ -1,3 +1,6 @@
+pub mod error;
+pub mod layout;
+pub mod payload;
 pub mod phi_base;
 
 pub mod game_key;
@@ -7,5 +10,18 @@ pub mod settings;
 pub mod summary;
 pub mod user;
 
+pub mod best_n;
+pub mod challenge;
+pub mod chart_table;
+pub mod diff;
+pub mod grade;
+pub mod merge;
+pub mod rks;
+
+#[cfg(feature = "encryption")]
+pub mod encryption;
+#[cfg(feature = "save_bundle")]
+pub mod save_bundle;
+
 #[cfg(feature = "c_abi")]
 mod c_api;
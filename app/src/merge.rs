@@ -0,0 +1,727 @@
+use crate::diff::Value;
+use crate::game_key::field::{GameKey, KEY_FIELDS, Key, KeyList};
+use crate::game_progress::field::{Chapter8Base, GameProgress, Money, ProgressBase};
+use crate::game_record::field::{GameRecord, SongEntry};
+use crate::game_record::song::{ChartRecord, Difficulty};
+use crate::phi_base::{Extra, PhiString, VARINT_MAX, VarInt};
+use crate::settings::field::{Settings, SettingsBase};
+use crate::user::field::User;
+use std::collections::BTreeMap;
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(rename_all = "snake_case"))]
+pub enum Side {
+    Base,
+    #[default]
+    Ours,
+    Theirs,
+}
+
+// 双方都改动了同一字段且无法合并时的取舍,可按路径单独指定,如 "settings.device_name"
+#[derive(Debug, Clone, Default)]
+pub struct MergePolicy {
+    pub default: Side,
+    pub fields: BTreeMap<String, Side>,
+}
+
+impl MergePolicy {
+    pub fn new(default: Side) -> Self {
+        MergePolicy {
+            default,
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn with(mut self, path: impl Into<String>, side: Side) -> Self {
+        self.fields.insert(path.into(), side);
+        self
+    }
+
+    pub fn side(&self, path: &str) -> Side {
+        self.fields.get(path).copied().unwrap_or(self.default)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+#[cfg_attr(feature = "serde", serde(tag = "kind", rename_all = "snake_case"))]
+pub enum MergeEvent {
+    Conflict {
+        path: String,
+        base: Value,
+        ours: Value,
+        theirs: Value,
+        resolved: Side,
+    },
+    Combined {
+        path: String,
+        ours: Value,
+        theirs: Value,
+        result: Value,
+    },
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct MergeReport {
+    pub events: Vec<MergeEvent>,
+}
+
+impl MergeReport {
+    pub fn conflicts(&self) -> impl Iterator<Item = &MergeEvent> {
+        self.events
+            .iter()
+            .filter(|e| matches!(e, MergeEvent::Conflict { .. }))
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts().next().is_some()
+    }
+}
+
+pub trait Merge: Sized {
+    fn merge(base: &Self, ours: &Self, theirs: &Self, policy: &MergePolicy) -> (Self, MergeReport);
+}
+
+struct Merger<'a> {
+    root: &'static str,
+    policy: &'a MergePolicy,
+    report: MergeReport,
+}
+
+impl<'a> Merger<'a> {
+    fn new(root: &'static str, policy: &'a MergePolicy) -> Self {
+        Merger {
+            root,
+            policy,
+            report: MergeReport::default(),
+        }
+    }
+
+    fn path(&self, name: &str) -> String {
+        format!("{}.{}", self.root, name)
+    }
+
+    // 只有一方改动时取改动的一方,双方都改动时按策略取舍并记为冲突
+    fn pick<T: Clone + PartialEq + Into<Value>>(
+        &mut self,
+        name: &str,
+        base: &T,
+        ours: &T,
+        theirs: &T,
+    ) -> T {
+        if ours == theirs || theirs == base {
+            return ours.clone();
+        }
+        if ours == base {
+            return theirs.clone();
+        }
+
+        let path = self.path(name);
+        let resolved = self.policy.side(&path);
+        let result = match resolved {
+            Side::Base => base.clone(),
+            Side::Ours => ours.clone(),
+            Side::Theirs => theirs.clone(),
+        };
+        self.report.events.push(MergeEvent::Conflict {
+            path,
+            base: base.clone().into(),
+            ours: ours.clone().into(),
+            theirs: theirs.clone().into(),
+            resolved,
+        });
+        result
+    }
+
+    // 只有一方改动时取改动的一方,双方都改动时用 f 合并两边的值
+    fn apply<T: Clone + PartialEq + Into<Value>>(
+        &mut self,
+        name: &str,
+        base: &T,
+        ours: &T,
+        theirs: &T,
+        f: impl FnOnce(&T, &T) -> T,
+    ) -> T {
+        if ours == base {
+            return theirs.clone();
+        }
+        if theirs == base {
+            return ours.clone();
+        }
+        self.combine(name, ours, theirs, f)
+    }
+
+    // 单调字段不看 base,两边不同时总是用 f 合并,避免一方的旧值覆盖另一方的新值
+    fn combine<T: Clone + PartialEq + Into<Value>>(
+        &mut self,
+        name: &str,
+        ours: &T,
+        theirs: &T,
+        f: impl FnOnce(&T, &T) -> T,
+    ) -> T {
+        if ours == theirs {
+            return ours.clone();
+        }
+
+        let result = f(ours, theirs);
+        self.report.events.push(MergeEvent::Combined {
+            path: self.path(name),
+            ours: ours.clone().into(),
+            theirs: theirs.clone().into(),
+            result: result.clone().into(),
+        });
+        result
+    }
+
+    fn max<T: Clone + PartialEq + PartialOrd + Into<Value>>(
+        &mut self,
+        name: &str,
+        ours: &T,
+        theirs: &T,
+    ) -> T {
+        self.combine(name, ours, theirs, |a, b| {
+            if b > a { b.clone() } else { a.clone() }
+        })
+    }
+
+    fn or(&mut self, name: &str, ours: bool, theirs: bool) -> bool {
+        self.combine(name, &ours, &theirs, |a, b| *a || *b)
+    }
+
+    fn flags<const N: usize>(
+        &mut self,
+        name: &str,
+        ours: &[bool; N],
+        theirs: &[bool; N],
+    ) -> [bool; N] {
+        std::array::from_fn(|i| self.or(&format!("{}[{}]", name, i), ours[i], theirs[i]))
+    }
+
+    fn string(
+        &mut self,
+        name: &str,
+        base: &PhiString,
+        ours: &PhiString,
+        theirs: &PhiString,
+    ) -> PhiString {
+        PhiString::from(self.pick(name, &base.0.as_str(), &ours.0.as_str(), &theirs.0.as_str()))
+    }
+
+    fn extra(&mut self, base: &Extra, ours: &Extra, theirs: &Extra) -> Extra {
+        Extra(
+            self.pick(
+                "extra",
+                &base.0.as_slice(),
+                &ours.0.as_slice(),
+                &theirs.0.as_slice(),
+            )
+            .to_vec(),
+        )
+    }
+}
+
+fn songs_by_name(record: &GameRecord) -> BTreeMap<&str, &SongEntry> {
+    record
+        .song_list
+        .iter()
+        .map(|s| (s.name.0.as_str(), s))
+        .collect()
+}
+
+// 成绩只增不减,逐谱面取最高分与最高 acc,FC 取或
+impl Merge for GameRecord {
+    fn merge(base: &Self, ours: &Self, theirs: &Self, policy: &MergePolicy) -> (Self, MergeReport) {
+        let mut m = Merger::new("game_record", policy);
+        let version = m.max("version", &ours.version, &theirs.version);
+
+        let our_songs = songs_by_name(ours);
+        let their_songs = songs_by_name(theirs);
+
+        // 保持我方的曲目顺序,对方独有的曲目追加在末尾
+        let names = ours.song_list.iter().chain(
+            theirs
+                .song_list
+                .iter()
+                .filter(|s| !our_songs.contains_key(s.name.0.as_str())),
+        );
+
+        let mut song_list = Vec::new();
+        for song in names {
+            let name = song.name.0.as_str();
+            // length 由 set 维护,这里先按没有难度时的长度初始化
+            let mut entry = SongEntry {
+                name: PhiString::from(name),
+                length: VarInt(2),
+                ..Default::default()
+            };
+
+            for diff in Difficulty::ALL {
+                let o = our_songs.get(name).and_then(|s| s.get(diff));
+                let t = their_songs.get(name).and_then(|s| s.get(diff));
+                let record = match (o, t) {
+                    (Some(o), Some(t)) => {
+                        let path = format!("{}.{}", name, diff);
+                        ChartRecord {
+                            score: m.max(&format!("{}.score", path), &o.score, &t.score),
+                            acc: m.max(&format!("{}.acc", path), &o.acc, &t.acc),
+                            fc: m.or(&format!("{}.fc", path), o.fc, t.fc),
+                        }
+                    }
+                    (Some(c), None) | (None, Some(c)) => c,
+                    (None, None) => continue,
+                };
+                entry.set(diff, record);
+            }
+            song_list.push(entry);
+        }
+
+        let extra = m.extra(&base.extra, &ours.extra, &theirs.extra);
+        let record = GameRecord {
+            version,
+            song_sum: VarInt(song_list.len() as u16),
+            song_list,
+            extra,
+        };
+        (record, m.report)
+    }
+}
+
+fn keys_by_name(gk: &GameKey) -> BTreeMap<&str, &Key> {
+    gk.key_list
+        .key_list
+        .iter()
+        .map(|k| (k.key.0.as_str(), k))
+        .collect()
+}
+
+// 收藏品数量取较大值,解锁标记取或;两者都存为 u8,统一取最大值即可
+impl Merge for GameKey {
+    fn merge(base: &Self, ours: &Self, theirs: &Self, policy: &MergePolicy) -> (Self, MergeReport) {
+        let mut m = Merger::new("game_key", policy);
+        let version = m.max("version", &ours.version, &theirs.version);
+
+        let our_keys = keys_by_name(ours);
+        let their_keys = keys_by_name(theirs);
+
+        let names = ours.key_list.key_list.iter().chain(
+            theirs
+                .key_list
+                .key_list
+                .iter()
+                .filter(|k| !our_keys.contains_key(k.key.0.as_str())),
+        );
+
+        let mut key_list = Vec::new();
+        for key in names {
+            let name = key.key.0.as_str();
+            let flags = match (our_keys.get(name), their_keys.get(name)) {
+                (Some(o), Some(t)) => {
+                    let (o, t) = (o.flags(), t.flags());
+                    std::array::from_fn(|i| {
+                        m.max(&format!("{}.{}", name, KEY_FIELDS[i]), &o[i], &t[i])
+                    })
+                }
+                (Some(k), None) | (None, Some(k)) => k.flags(),
+                (None, None) => continue,
+            };
+            key_list.push(Key::from_flags(name, flags));
+        }
+
+        let game_key = GameKey {
+            version,
+            key_list: KeyList {
+                key_sum: VarInt(key_list.len() as u16),
+                key_list,
+            },
+            lanota_read_keys: m.flags(
+                "lanota_read_keys",
+                &ours.lanota_read_keys,
+                &theirs.lanota_read_keys,
+            ),
+            camellia_read_key: m.max(
+                "camellia_read_key",
+                &ours.camellia_read_key,
+                &theirs.camellia_read_key,
+            ),
+            side_story4_begin_read_key: m.max(
+                "side_story4_begin_read_key",
+                &ours.side_story4_begin_read_key,
+                &theirs.side_story4_begin_read_key,
+            ),
+            old_score_cleared_v390: m.max(
+                "old_score_cleared_v390",
+                &ours.old_score_cleared_v390,
+                &theirs.old_score_cleared_v390,
+            ),
+            extra: m.extra(&base.extra, &ours.extra, &theirs.extra),
+        };
+        (game_key, m.report)
+    }
+}
+
+// 以 KiB 为单位的总数
+fn money_total(m: &Money) -> i64 {
+    (m.to_bytes() / 1024) as i64
+}
+
+// 超出表示范围时取最大值
+fn money_from_total(total: i64) -> Money {
+    let max = Money {
+        kib: VarInt(1023),
+        mib: VarInt(1023),
+        gib: VarInt(1023),
+        tib: VarInt(1023),
+        pib: VarInt(VARINT_MAX),
+    };
+    Money::from_bytes(total.max(0) as u128 * 1024).unwrap_or(max)
+}
+
+impl Merge for GameProgress {
+    fn merge(base: &Self, ours: &Self, theirs: &Self, policy: &MergePolicy) -> (Self, MergeReport) {
+        let mut m = Merger::new("game_progress", policy);
+
+        // 两边的收支都计入: base + (ours - base) + (theirs - base)
+        let (b, o, t) = (
+            money_total(&base.money),
+            money_total(&ours.money),
+            money_total(&theirs.money),
+        );
+        let money = money_from_total(m.apply("money", &b, &o, &t, |o, t| o + t - b));
+
+        let (o8, t8) = (&ours.chapter8_base, &theirs.chapter8_base);
+        let takumi = match (
+            &ours.flag_of_song_record_key_takumi,
+            &theirs.flag_of_song_record_key_takumi,
+        ) {
+            (Some(o), Some(t)) => Some(m.flags("flag_of_song_record_key_takumi", o, t)),
+            (o, t) => o.or(*t),
+        };
+
+        let progress = GameProgress {
+            version: m.max("version", &ours.version, &theirs.version),
+            base: ProgressBase {
+                is_first_run: m.combine(
+                    "base.is_first_run",
+                    &ours.base.is_first_run,
+                    &theirs.base.is_first_run,
+                    |a, b| *a && *b,
+                ),
+                legacy_chapter_finished: m.or(
+                    "base.legacy_chapter_finished",
+                    ours.base.legacy_chapter_finished,
+                    theirs.base.legacy_chapter_finished,
+                ),
+                already_show_collection_tip: m.or(
+                    "base.already_show_collection_tip",
+                    ours.base.already_show_collection_tip,
+                    theirs.base.already_show_collection_tip,
+                ),
+                already_show_auto_unlock_in_tip: m.or(
+                    "base.already_show_auto_unlock_in_tip",
+                    ours.base.already_show_auto_unlock_in_tip,
+                    theirs.base.already_show_auto_unlock_in_tip,
+                ),
+            },
+            completed: m.string(
+                "completed",
+                &base.completed,
+                &ours.completed,
+                &theirs.completed,
+            ),
+            song_update_info: VarInt(m.max(
+                "song_update_info",
+                &ours.song_update_info.0,
+                &theirs.song_update_info.0,
+            )),
+            challenge_mode_rank: m.pick(
+                "challenge_mode_rank",
+                &base.challenge_mode_rank,
+                &ours.challenge_mode_rank,
+                &theirs.challenge_mode_rank,
+            ),
+            money,
+            unlock_flag_of_spasmodic: m.flags(
+                "unlock_flag_of_spasmodic",
+                &ours.unlock_flag_of_spasmodic,
+                &theirs.unlock_flag_of_spasmodic,
+            ),
+            unlock_flag_of_igallta: m.flags(
+                "unlock_flag_of_igallta",
+                &ours.unlock_flag_of_igallta,
+                &theirs.unlock_flag_of_igallta,
+            ),
+            unlock_flag_of_rrharil: m.flags(
+                "unlock_flag_of_rrharil",
+                &ours.unlock_flag_of_rrharil,
+                &theirs.unlock_flag_of_rrharil,
+            ),
+            flag_of_song_record_key: m.flags(
+                "flag_of_song_record_key",
+                &ours.flag_of_song_record_key,
+                &theirs.flag_of_song_record_key,
+            ),
+            random_version_unlocked: m.flags(
+                "random_version_unlocked",
+                &ours.random_version_unlocked,
+                &theirs.random_version_unlocked,
+            ),
+            chapter8_base: Chapter8Base {
+                unlock_begin: m.or(
+                    "chapter8_base.unlock_begin",
+                    o8.unlock_begin,
+                    t8.unlock_begin,
+                ),
+                unlock_second_phase: m.or(
+                    "chapter8_base.unlock_second_phase",
+                    o8.unlock_second_phase,
+                    t8.unlock_second_phase,
+                ),
+                passed: m.or("chapter8_base.passed", o8.passed, t8.passed),
+            },
+            chapter8_song_unlocked: m.flags(
+                "chapter8_song_unlocked",
+                &ours.chapter8_song_unlocked,
+                &theirs.chapter8_song_unlocked,
+            ),
+            flag_of_song_record_key_takumi: takumi,
+            extra: m.extra(&base.extra, &ours.extra, &theirs.extra),
+        };
+        (progress, m.report)
+    }
+}
+
+impl Merge for Settings {
+    fn merge(base: &Self, ours: &Self, theirs: &Self, policy: &MergePolicy) -> (Self, MergeReport) {
+        let mut m = Merger::new("settings", policy);
+        let (bb, ob, tb) = (&base.base, &ours.base, &theirs.base);
+
+        let settings = Settings {
+            version: m.max("version", &ours.version, &theirs.version),
+            base: SettingsBase {
+                chord_support: m.pick(
+                    "base.chord_support",
+                    &bb.chord_support,
+                    &ob.chord_support,
+                    &tb.chord_support,
+                ),
+                fc_ap_indicator: m.pick(
+                    "base.fc_ap_indicator",
+                    &bb.fc_ap_indicator,
+                    &ob.fc_ap_indicator,
+                    &tb.fc_ap_indicator,
+                ),
+                enable_hit_sound: m.pick(
+                    "base.enable_hit_sound",
+                    &bb.enable_hit_sound,
+                    &ob.enable_hit_sound,
+                    &tb.enable_hit_sound,
+                ),
+                low_resolution_mode: m.pick(
+                    "base.low_resolution_mode",
+                    &bb.low_resolution_mode,
+                    &ob.low_resolution_mode,
+                    &tb.low_resolution_mode,
+                ),
+            },
+            device_name: m.string(
+                "device_name",
+                &base.device_name,
+                &ours.device_name,
+                &theirs.device_name,
+            ),
+            bright: m.pick("bright", &base.bright, &ours.bright, &theirs.bright),
+            music_volume: m.pick(
+                "music_volume",
+                &base.music_volume,
+                &ours.music_volume,
+                &theirs.music_volume,
+            ),
+            effect_volume: m.pick(
+                "effect_volume",
+                &base.effect_volume,
+                &ours.effect_volume,
+                &theirs.effect_volume,
+            ),
+            hit_sound_volume: m.pick(
+                "hit_sound_volume",
+                &base.hit_sound_volume,
+                &ours.hit_sound_volume,
+                &theirs.hit_sound_volume,
+            ),
+            sound_offset: m.pick(
+                "sound_offset",
+                &base.sound_offset,
+                &ours.sound_offset,
+                &theirs.sound_offset,
+            ),
+            note_scale: m.pick(
+                "note_scale",
+                &base.note_scale,
+                &ours.note_scale,
+                &theirs.note_scale,
+            ),
+            extra: m.extra(&base.extra, &ours.extra, &theirs.extra),
+        };
+        (settings, m.report)
+    }
+}
+
+impl Merge for User {
+    fn merge(base: &Self, ours: &Self, theirs: &Self, policy: &MergePolicy) -> (Self, MergeReport) {
+        let mut m = Merger::new("user", policy);
+
+        let user = User {
+            version: m.max("version", &ours.version, &theirs.version),
+            show_player_id: m.pick(
+                "show_player_id",
+                &base.show_player_id,
+                &ours.show_player_id,
+                &theirs.show_player_id,
+            ),
+            self_intro: m.string(
+                "self_intro",
+                &base.self_intro,
+                &ours.self_intro,
+                &theirs.self_intro,
+            ),
+            avatar: m.string("avatar", &base.avatar, &ours.avatar, &theirs.avatar),
+            background: m.string(
+                "background",
+                &base.background,
+                &ours.background,
+                &theirs.background,
+            ),
+            extra: m.extra(&base.extra, &ours.extra, &theirs.extra),
+        };
+        (user, m.report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game_key::entry::KeyEntry;
+
+    const SONG: &str = "Glaciaxion.SunsetRay.0";
+
+    fn record(score: u32, acc: f32, fc: bool) -> GameRecord {
+        let mut r = GameRecord::default();
+        r.set(SONG, Difficulty::IN, ChartRecord { score, acc, fc });
+        r
+    }
+
+    fn merged_chart(base: GameRecord, ours: GameRecord, theirs: GameRecord) -> ChartRecord {
+        let (merged, _) = GameRecord::merge(&base, &ours, &theirs, &MergePolicy::default());
+        merged.get(SONG, Difficulty::IN).unwrap()
+    }
+
+    #[test]
+    fn fc_is_kept_when_other_side_lost_it() {
+        let chart = merged_chart(
+            record(990000, 99.0, true),
+            record(990000, 99.0, true),
+            record(990000, 99.0, false),
+        );
+        assert!(chart.fc);
+    }
+
+    #[test]
+    fn higher_score_is_kept_when_other_side_regressed() {
+        let chart = merged_chart(
+            record(990000, 99.0, false),
+            record(990000, 99.0, false),
+            record(900000, 95.0, false),
+        );
+        assert_eq!(chart.score, 990000);
+        assert_eq!(chart.acc, 99.0);
+    }
+
+    #[test]
+    fn chart_takes_best_of_each_field() {
+        let base = record(800000, 90.0, false);
+        let (merged, report) = GameRecord::merge(
+            &base,
+            &record(950000, 97.0, false),
+            &record(900000, 98.0, true),
+            &MergePolicy::default(),
+        );
+        let chart = merged.get(SONG, Difficulty::IN).unwrap();
+        assert_eq!(chart.score, 950000);
+        assert_eq!(chart.acc, 98.0);
+        assert!(chart.fc);
+        assert_eq!(report.events.len(), 3);
+        assert!(!report.has_conflicts());
+    }
+
+    #[test]
+    fn key_counts_take_max() {
+        let key = |n: u8| {
+            let mut gk = GameKey::default();
+            gk.set("key", KeyEntry::collection(n, n));
+            gk
+        };
+        let (merged, _) = GameKey::merge(&key(3), &key(3), &key(1), &MergePolicy::default());
+        assert_eq!(merged.get("key"), Some(KeyEntry::collection(3, 3)));
+    }
+
+    #[test]
+    fn unlock_flags_are_ored() {
+        let progress = |flag: bool| GameProgress {
+            unlock_flag_of_igallta: [flag; 4],
+            ..Default::default()
+        };
+        let (merged, _) = GameProgress::merge(
+            &progress(true),
+            &progress(true),
+            &progress(false),
+            &MergePolicy::default(),
+        );
+        assert_eq!(merged.unlock_flag_of_igallta, [true; 4]);
+    }
+
+    #[test]
+    fn money_applies_both_sides_changes() {
+        let progress = |kib: u16| GameProgress {
+            money: Money {
+                kib: VarInt(kib),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let merge = |b, o, t| {
+            let (merged, _) = GameProgress::merge(
+                &progress(b),
+                &progress(o),
+                &progress(t),
+                &MergePolicy::default(),
+            );
+            merged.money.to_bytes() / 1024
+        };
+        assert_eq!(merge(100, 150, 80), 130);
+        assert_eq!(merge(100, 100, 80), 80);
+        assert_eq!(merge(100, 150, 100), 150);
+    }
+
+    #[test]
+    fn policy_fields_only_conflict_when_both_changed() {
+        let settings = |name: &str| Settings {
+            device_name: PhiString::from(name),
+            ..Default::default()
+        };
+        let policy = MergePolicy::new(Side::Theirs);
+        let (merged, report) =
+            Settings::merge(&settings("a"), &settings("a"), &settings("b"), &policy);
+        assert_eq!(merged.device_name.0, "b");
+        assert!(!report.has_conflicts());
+
+        let (merged, report) =
+            Settings::merge(&settings("a"), &settings("c"), &settings("b"), &policy);
+        assert_eq!(merged.device_name.0, "b");
+        assert!(report.has_conflicts());
+    }
+}
@@ -0,0 +1,394 @@
+use crate::error::{IntoPscError, PscError};
+use crate::layout::FieldSpan;
+use crate::phi_base::{Extra, lossy_utf8};
+use shua_struct::{BinaryField, BitField, BitSlice, Lsb0};
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ParseOptions {
+    pub strict: bool,
+    pub max_version: Option<u8>,
+    // 拒绝未进位的 Money (某个单位不小于 1024),strict 时总是检查
+    pub reject_unnormalized_money: bool,
+}
+
+impl ParseOptions {
+    pub fn strict() -> Self {
+        ParseOptions {
+            strict: true,
+            ..Default::default()
+        }
+    }
+
+    // 是否需要对字段取值做额外检查
+    fn checks_values(&self) -> bool {
+        self.strict || self.reject_unnormalized_money
+    }
+}
+
+pub trait Payload: BinaryField<Lsb0, Error: IntoPscError> + Sized {
+    const NAME: &'static str;
+    const VERSION_FIELD: &'static str = "version";
+    // None 表示布局不随版本变化
+    const MAX_VERSION: Option<u8>;
+
+    fn version(&self) -> u8;
+
+    fn extra(&self) -> &Extra;
+
+    // 按字段逐个走一遍二进制数据,结构需与 field.rs 中的定义保持一致
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError>;
+
+    // 按 options 对字段取值做额外检查,layout 用于定位出错字段
+    fn check(&self, _layout: &FieldSpan, _options: &ParseOptions) -> Result<(), PscError> {
+        Ok(())
+    }
+
+    fn parse_bytes(bytes: &[u8]) -> Result<Self, PscError> {
+        let bits = BitSlice::<u8, Lsb0>::from_slice(bytes);
+        Self::parse(bits, &()).map_err(|e| {
+            let mut w = Walker::new(Self::NAME, bytes);
+            match Self::walk(&mut w) {
+                Err(located) => located,
+                Ok(()) => e.into_psc_error(Self::NAME, Some(&w.into_layout())),
+            }
+        })
+    }
+
+    // 严格模式下拒绝多余数据、未知版本、不规范的 VarInt 与不合法的取值
+    fn parse_with(bytes: &[u8], options: &ParseOptions) -> Result<Self, PscError> {
+        let item = Self::parse_bytes(bytes)?;
+
+        let mut limit = options.max_version;
+        if options.strict
+            && let Some(max) = Self::MAX_VERSION
+        {
+            limit = Some(limit.map_or(max, |m| m.min(max)));
+        }
+        if let Some(max) = limit
+            && item.version() > max
+        {
+            return Err(PscError::UnsupportedVersion {
+                path: format!("{}.{}", Self::NAME, Self::VERSION_FIELD),
+                bit_offset: 0,
+                version: item.version(),
+                max,
+            });
+        }
+
+        if options.checks_values() {
+            let mut w = Walker::new(Self::NAME, bytes);
+            w.strict = options.strict;
+            Self::walk(&mut w)?;
+            item.check(&w.into_layout(), options)?;
+        }
+
+        if options.strict {
+            let extra = item.extra();
+            if !extra.is_empty() {
+                return Err(PscError::TrailingData {
+                    path: format!("{}.extra", Self::NAME),
+                    bit_offset: (bytes.len() - extra.0.len()) * 8,
+                    len: extra.0.len(),
+                });
+            }
+        }
+
+        Ok(item)
+    }
+
+    fn build_bytes(&self) -> Result<Vec<u8>, PscError> {
+        self.to_bitvec(&())
+            .map(|bv| bv.into_vec())
+            .map_err(|e| e.into_psc_error(Self::NAME, None))
+    }
+}
+
+pub struct Walker<'a> {
+    bytes: &'a [u8],
+    bits: &'a BitSlice<u8, Lsb0>,
+    offset: usize,
+    path: Vec<String>,
+    strict: bool,
+    // 栈底为根节点,nest 时压入子节点
+    spans: Vec<FieldSpan>,
+}
+
+#[inline]
+fn align_up(len: usize, align: usize) -> usize {
+    if align == 0 {
+        len
+    } else {
+        len.div_ceil(align) * align
+    }
+}
+
+impl<'a> Walker<'a> {
+    pub fn new(root: &str, bytes: &'a [u8]) -> Self {
+        Walker {
+            bytes,
+            bits: BitSlice::from_slice(bytes),
+            offset: 0,
+            path: vec![root.to_string()],
+            strict: false,
+            spans: vec![FieldSpan::group(root, 0)],
+        }
+    }
+
+    pub fn into_layout(mut self) -> FieldSpan {
+        let mut root = self.spans.swap_remove(0);
+        root.bit_len = self.offset;
+        root
+    }
+
+    fn record(&mut self, name: &str, start: usize, padding_bits: usize) {
+        let bit_len = self.offset - start - padding_bits;
+        let end = (start + bit_len).div_ceil(8).min(self.bytes.len());
+        let span = FieldSpan {
+            name: name.to_string(),
+            bit_offset: start,
+            bit_len,
+            padding_bits,
+            bytes: self.bytes[start / 8..end].to_vec(),
+            children: Vec::new(),
+        };
+        if let Some(parent) = self.spans.last_mut() {
+            parent.children.push(span);
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn path(&self) -> String {
+        self.path.concat()
+    }
+
+    fn child(&self, name: &str) -> String {
+        format!("{}.{}", self.path(), name)
+    }
+
+    fn remaining(&self) -> usize {
+        self.bits.len().saturating_sub(self.offset)
+    }
+
+    fn truncated(&self, name: &str, needed: usize) -> PscError {
+        PscError::Truncated {
+            path: self.child(name),
+            bit_offset: self.offset,
+            needed,
+            available: self.remaining(),
+        }
+    }
+
+    pub fn read<T>(&mut self, name: &str) -> Result<T, PscError>
+    where
+        T: BinaryField<Lsb0> + Default,
+    {
+        self.read_aligned(name, 0)
+    }
+
+    // 只适用于定长字段,对齐作用于字段长度
+    pub fn read_aligned<T>(&mut self, name: &str, align: usize) -> Result<T, PscError>
+    where
+        T: BinaryField<Lsb0> + Default,
+    {
+        let needed = T::default().bit_len(&());
+        if self.remaining() < needed {
+            return Err(self.truncated(name, needed));
+        }
+        let value =
+            T::parse(&self.bits[self.offset..], &()).map_err(|_| self.truncated(name, needed))?;
+        let start = self.offset;
+        self.offset += align_up(needed, align);
+        self.record(name, start, align_up(needed, align) - needed);
+        Ok(value)
+    }
+
+    pub fn varint(&mut self, name: &str) -> Result<u16, PscError> {
+        let start = self.offset;
+        let value = self.load_varint(name)?;
+        self.record(name, start, 0);
+        Ok(value)
+    }
+
+    fn load_varint(&mut self, name: &str) -> Result<u16, PscError> {
+        if self.remaining() < 8 {
+            return Err(self.truncated(name, 8));
+        }
+        let first = self.bits[self.offset..self.offset + 8].load_le::<u8>();
+        if first <= 127 {
+            self.offset += 8;
+            return Ok(first as u16);
+        }
+
+        if self.remaining() < 16 {
+            return Err(self.truncated(name, 16));
+        }
+        let second = self.bits[self.offset + 8..self.offset + 16].load_le::<u8>();
+        if second > 127 {
+            return Err(PscError::VarIntOutOfRange {
+                path: self.child(name),
+                bit_offset: self.offset,
+            });
+        }
+        let value = ((first & 0x7F) as u16) | ((second as u16) << 7);
+        if self.strict && value <= 127 {
+            return Err(PscError::NonCanonicalVarInt {
+                path: self.child(name),
+                bit_offset: self.offset,
+                value,
+            });
+        }
+        self.offset += 16;
+        Ok(value)
+    }
+
+    pub fn string(&mut self, name: &str) -> Result<String, PscError> {
+        let start = self.offset;
+        let len = self.load_varint(name)? as usize;
+        if self.remaining() < len * 8 {
+            let header = self.offset - start;
+            self.offset = start;
+            return Err(self.truncated(name, header + len * 8));
+        }
+
+        let bytes: Vec<u8> = (0..len)
+            .map(|i| {
+                let at = self.offset + i * 8;
+                self.bits[at..at + 8].load_le::<u8>()
+            })
+            .collect();
+        // 宽松模式与 PhiString::parse 一致,非法字节按 lossy 处理
+        let s = match String::from_utf8(bytes) {
+            Ok(s) => s,
+            Err(_) if self.strict => {
+                return Err(PscError::InvalidString {
+                    path: self.child(name),
+                    bit_offset: start,
+                });
+            }
+            Err(e) => lossy_utf8(e.as_bytes()),
+        };
+        self.offset += len * 8;
+        self.record(name, start, 0);
+        Ok(s)
+    }
+
+    pub fn raw(&mut self, name: &str, len: usize) -> Result<(), PscError> {
+        if self.remaining() < len * 8 {
+            return Err(self.truncated(name, len * 8));
+        }
+        let start = self.offset;
+        self.offset += len * 8;
+        self.record(name, start, 0);
+        Ok(())
+    }
+
+    pub fn rest(&mut self, name: &str) -> usize {
+        let start = self.offset;
+        let len = self.remaining();
+        self.offset += len;
+        if len > 0 {
+            self.record(name, start, 0);
+        }
+        len
+    }
+
+    fn group<R>(
+        &mut self,
+        segment: String,
+        name: String,
+        f: impl FnOnce(&mut Self) -> Result<R, PscError>,
+    ) -> Result<R, PscError> {
+        self.path.push(segment);
+        self.spans.push(FieldSpan::group(name, self.offset));
+        let r = f(self);
+        self.path.pop();
+
+        if let Some(mut span) = self.spans.pop() {
+            span.bit_len = self.offset - span.bit_offset;
+            if let Some(parent) = self.spans.last_mut() {
+                parent.children.push(span);
+            }
+        }
+        r
+    }
+
+    pub fn nest<R>(
+        &mut self,
+        name: &str,
+        f: impl FnOnce(&mut Self) -> Result<R, PscError>,
+    ) -> Result<R, PscError> {
+        self.group(format!(".{}", name), name.to_string(), f)
+    }
+
+    pub fn list(
+        &mut self,
+        name: &str,
+        count: usize,
+        mut f: impl FnMut(&mut Self) -> Result<(), PscError>,
+    ) -> Result<(), PscError> {
+        self.nest(name, |w| {
+            for i in 0..count {
+                let index = format!("[{}]", i);
+                w.group(index.clone(), index, &mut f)?;
+            }
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    const TRAILER: [u8; 3] = [0xAB, 0xCD, 0xEF];
+
+    // 派生的 Debug 输出中顶层字段的名字,按声明顺序,跳过当前版本不存在的 None 字段
+    fn derived_fields<T: std::fmt::Debug>(item: &T) -> Vec<String> {
+        format!("{:#?}", item)
+            .lines()
+            .filter_map(|line| line.strip_prefix("    "))
+            .filter(|line| !line.starts_with(' '))
+            .filter_map(|line| line.split_once(": "))
+            .filter(|(_, value)| *value != "None,")
+            .map(|(name, _)| name.to_string())
+            .collect()
+    }
+
+    // walk 需与派生的 parse 在同一位置结束: 追加的字节在两边都应被归入 extra,
+    // 且顶层字段与结构体定义一一对应,新增字段而未修改 walk 时会在这里失败
+    pub(crate) fn assert_walk_matches_parse<T: Payload + std::fmt::Debug>(item: &T) {
+        let mut bytes = item.build_bytes().unwrap();
+        let end = bytes.len() * 8 - item.extra().0.len() * 8;
+        bytes.extend_from_slice(&TRAILER);
+
+        let parsed = T::parse_bytes(&bytes).unwrap();
+        assert!(parsed.extra().0.ends_with(&TRAILER));
+        assert_eq!(bytes.len() * 8 - parsed.extra().0.len() * 8, end);
+
+        let mut w = Walker::new(T::NAME, &bytes);
+        T::walk(&mut w).unwrap();
+        let layout = w.into_layout();
+        let extra = layout.find("extra").unwrap();
+        assert_eq!(extra.bit_offset, end, "{} 的 walk 与 parse 不一致", T::NAME);
+
+        // 低版本缺少按版本出现的字段,只要求 walk 的字段按顺序出现在定义中
+        let walked: Vec<&str> = layout.children.iter().map(|c| c.name.as_str()).collect();
+        let derived = derived_fields(item);
+        if T::MAX_VERSION.is_none_or(|max| item.version() >= max) {
+            assert_eq!(walked, derived, "{} 的 walk 与结构体字段不一致", T::NAME);
+        } else {
+            let mut rest = derived.iter();
+            for name in &walked {
+                assert!(
+                    rest.any(|d| d == name),
+                    "{} 的 walk 字段 {} 不在结构体定义中或顺序不同",
+                    T::NAME,
+                    name
+                );
+            }
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use shua_struct::{BinaryError, BinaryField, BitField, BitSlice, Lsb0};
 
+pub const VARINT_MAX: u16 = 0x7FFF;
+
 #[derive(Clone, Copy, Debug, Default)]
 pub struct VarInt(pub u16);
 
@@ -20,6 +22,9 @@ impl BinaryField<Lsb0> for VarInt {
             }
 
             let second = bits[8..16].load_le::<u8>();
+            if second > 127 {
+                return Err(Self::Error::Custom(()));
+            }
             let value = ((first & 0x7F) as u16) | ((second as u16) << 7);
             Ok(VarInt(value))
         } else {
@@ -37,6 +42,10 @@ impl BinaryField<Lsb0> for VarInt {
             }
         }
 
+        if self.0 > VARINT_MAX {
+            return Err(Self::Error::Custom(()));
+        }
+
         if self.0 > 127 {
             let first = ((self.0 & 0x7F) as u8) | 0x80;
             let second = (self.0 >> 7) as u8;
@@ -73,9 +82,21 @@ impl From<VarInt> for u16 {
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 pub struct PhiString(pub String);
 
+// 派生的 parse 按解析出的值的 bit_len 前进,而 PhiString 的 bit_len 由字符串的字节长度算出。
+// from_utf8_lossy 的 U+FFFD 占 3 字节,会让后续字段从错误的位置开始解析,
+// 因此非法字节逐个替换为单字节的 '?'
+pub(crate) fn lossy_utf8(bytes: &[u8]) -> String {
+    let mut s = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        s.push_str(chunk.valid());
+        s.extend(chunk.invalid().iter().map(|_| '?'));
+    }
+    s
+}
+
 impl BinaryField<Lsb0> for PhiString {
     type Error = BinaryError;
 
@@ -102,8 +123,7 @@ impl BinaryField<Lsb0> for PhiString {
             bytes.push(bits[start..end].load_le::<u8>());
         }
 
-        let s = String::from_utf8_lossy(&bytes).to_string();
-        Ok(PhiString(s))
+        Ok(PhiString(lossy_utf8(&bytes)))
     }
 
     #[inline]
@@ -153,3 +173,86 @@ impl From<PhiString> for String {
         phi.0
     }
 }
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Extra(pub Vec<u8>);
+
+impl Extra {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// 吞掉剩余的所有数据,用于保留新版本追加的未知字段
+impl BinaryField<Lsb0> for Extra {
+    type Error = BinaryError;
+
+    #[inline]
+    fn parse(bits: &BitSlice<u8, Lsb0>, _ctx: &()) -> Result<Self, Self::Error> {
+        let bytes = bits.chunks(8).map(|chunk| chunk.load_le::<u8>()).collect();
+        Ok(Extra(bytes))
+    }
+
+    #[inline]
+    fn build(&self, bits: &mut BitSlice<u8, Lsb0>, _ctx: &()) -> Result<(), Self::Error> {
+        #[cfg(debug_assertions)]
+        {
+            let required_bits = self.0.len() * 8;
+            if bits.len() < required_bits {
+                return Err(Self::Error::bit_count_mismatch(required_bits, bits.len()));
+            }
+        }
+        for (i, byte) in self.0.iter().enumerate() {
+            bits[i * 8..i * 8 + 8].store_le(*byte);
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn bit_len(&self, _ctx: &()) -> usize {
+        self.0.len() * 8
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for Extra {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        use base64::Engine;
+        serializer.serialize_str(&base64::engine::general_purpose::STANDARD.encode(&self.0))
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Extra {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        use base64::Engine;
+        let s = <String as serde::Deserialize>::deserialize(deserializer)?;
+        base64::engine::general_purpose::STANDARD
+            .decode(s)
+            .map(Extra)
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 含非法字节的字符串解析后长度不变,其后的字段仍从正确的位置开始
+    #[test]
+    fn invalid_utf8_keeps_byte_length() {
+        let text = [b'a', 0xFF, 0xFE, b'b'];
+        let mut bytes = vec![text.len() as u8];
+        bytes.extend_from_slice(&text);
+        bytes.push(42);
+        let bits = BitSlice::<u8, Lsb0>::from_slice(&bytes);
+
+        let s = PhiString::parse(bits, &()).unwrap();
+        assert_eq!(s.0, "a??b");
+        assert_eq!(s.bit_len(&()), (1 + text.len()) * 8);
+        assert_eq!(u8::parse(&bits[s.bit_len(&())..], &()).unwrap(), 42);
+
+        assert_ne!(String::from_utf8_lossy(&text).len(), text.len());
+    }
+}
@@ -0,0 +1,196 @@
+use crate::game_record::field::{GameRecord, LevelRecord, SongEntry};
+use crate::game_record::song::Difficulty;
+use crate::summary::field::Summary;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::BuildHasher;
+
+pub const BEST_N: usize = 27;
+pub const BEST_PHI: usize = 3;
+pub const PHI_SCORE: u32 = 1_000_000;
+const RKS_EPSILON: f32 = 0.005;
+
+pub trait ChartConstants {
+    fn constant(&self, song_id: &str, difficulty: Difficulty) -> Option<f32>;
+}
+
+impl<S: BuildHasher> ChartConstants for HashMap<String, Vec<f32>, S> {
+    fn constant(&self, song_id: &str, difficulty: Difficulty) -> Option<f32> {
+        self.get(song_id)?.get(difficulty.index()).copied()
+    }
+}
+
+impl ChartConstants for BTreeMap<String, Vec<f32>> {
+    fn constant(&self, song_id: &str, difficulty: Difficulty) -> Option<f32> {
+        self.get(song_id)?.get(difficulty.index()).copied()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LevelRks {
+    pub song_id: String,
+    pub difficulty: Difficulty,
+    pub score: u32,
+    pub acc: f32,
+    pub fc: bool,
+    pub constant: f32,
+    pub rks: f32,
+}
+
+impl LevelRks {
+    pub fn is_phi(&self) -> bool {
+        self.score >= PHI_SCORE
+    }
+}
+
+// acc 低于 70% 不计分
+pub fn chart_rks(acc: f32, constant: f32) -> f32 {
+    if acc < 70.0 {
+        return 0.0;
+    }
+    let factor = (acc.min(100.0) - 55.0) / 45.0;
+    factor * factor * constant
+}
+
+pub(crate) fn song_levels(song: &SongEntry) -> impl Iterator<Item = (Difficulty, &LevelRecord)> {
+    Difficulty::ALL
+        .into_iter()
+        .filter(|d| song.unlock[d.index()])
+        .zip(song.levels.iter())
+}
+
+// 按单曲 RKS 从高到低排序,缺少定数的谱面会被跳过
+pub fn level_rks_list<C: ChartConstants + ?Sized>(
+    record: &GameRecord,
+    constants: &C,
+) -> Vec<LevelRks> {
+    let mut list: Vec<LevelRks> = record
+        .song_list
+        .iter()
+        .flat_map(|song| {
+            song_levels(song).filter_map(move |(difficulty, level)| {
+                let constant = constants.constant(&song.name.0, difficulty)?;
+                Some(LevelRks {
+                    song_id: song.name.0.clone(),
+                    difficulty,
+                    score: level.score,
+                    acc: level.acc,
+                    fc: song.fc[difficulty.index()],
+                    constant,
+                    rks: chart_rks(level.acc, constant),
+                })
+            })
+        })
+        .collect();
+
+    list.sort_by(|a, b| b.rks.total_cmp(&a.rks));
+    list
+}
+
+// 游戏内的规则: 最佳 27 张谱面 + 最佳 3 张 φ 谱面,总数固定除以 30
+pub fn player_rks(sorted: &[LevelRks]) -> f32 {
+    player_rks_n(sorted, BEST_N)
+}
+
+// 最佳 n 张谱面 + 最佳 φ 谱面,总数固定除以 n + φ
+pub fn player_rks_n(sorted: &[LevelRks], n: usize) -> f32 {
+    let best: f64 = sorted.iter().take(n).map(|l| l.rks as f64).sum();
+    let phi: f64 = sorted
+        .iter()
+        .filter(|l| l.is_phi())
+        .take(BEST_PHI)
+        .map(|l| l.rks as f64)
+        .sum();
+
+    ((best + phi) / (n + BEST_PHI) as f64) as f32
+}
+
+pub fn compute<C: ChartConstants + ?Sized>(record: &GameRecord, constants: &C) -> f32 {
+    player_rks(&level_rks_list(record, constants))
+}
+
+impl Summary {
+    pub fn rks_matches(&self, rks: f32) -> bool {
+        (self.rks - rks).abs() < RKS_EPSILON
+    }
+
+    pub fn update_rks<C: ChartConstants + ?Sized>(&mut self, record: &GameRecord, constants: &C) {
+        self.rks = compute(record, constants);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chart_table::ChartTable;
+    use crate::game_record::song::ChartRecord;
+
+    #[test]
+    fn acc_below_70_scores_nothing() {
+        assert_eq!(chart_rks(69.99, 15.0), 0.0);
+        assert_eq!(chart_rks(0.0, 15.0), 0.0);
+    }
+
+    // acc 为 70 时系数为 (15 / 45)² = 1/9
+    #[test]
+    fn acc_70_boundary() {
+        assert!((chart_rks(70.0, 15.0) - 15.0 / 9.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn full_acc_equals_constant() {
+        for constant in [1.0, 12.3, 16.9] {
+            assert_eq!(chart_rks(100.0, constant), constant);
+        }
+        assert_eq!(chart_rks(100.5, 12.0), 12.0);
+    }
+
+    #[test]
+    fn known_value() {
+        // ((99.5 - 55) / 45)² * 16 = 15.646...
+        let expected = (44.5f32 / 45.0).powi(2) * 16.0;
+        assert!((chart_rks(99.5, 16.0) - expected).abs() < 1e-5);
+        assert!((expected - 15.6464).abs() < 1e-3);
+    }
+
+    fn chart(score: u32, acc: f32) -> ChartRecord {
+        ChartRecord {
+            score,
+            acc,
+            fc: score == PHI_SCORE,
+        }
+    }
+
+    // φ 谱面既计入最佳 27 张,也计入最佳 3 张 φ,总和除以 30
+    #[test]
+    fn phi_charts_count_in_both_lists() {
+        let mut record = GameRecord::default();
+        let mut table = ChartTable::new();
+        for i in 0..30 {
+            let song = format!("song{}.artist.0", i);
+            let constant = 10.0 + i as f32 * 0.1;
+            let record_chart = if i >= 28 {
+                chart(PHI_SCORE, 100.0)
+            } else {
+                chart(980_000, 99.0)
+            };
+            record.set(&song, Difficulty::IN, record_chart);
+            table.insert(song, [None, None, Some(constant), None, None]);
+        }
+
+        let sorted = level_rks_list(&record, &table);
+        let best: f32 = sorted.iter().take(BEST_N).map(|l| l.rks).sum();
+        let phi = 12.9 + 12.8;
+        let expected = (best + phi) / 30.0;
+        assert!((player_rks(&sorted) - expected).abs() < 1e-4);
+        assert!(sorted[..2].iter().all(LevelRks::is_phi));
+    }
+
+    #[test]
+    fn no_phi_charts_still_divides_by_30() {
+        let mut record = GameRecord::default();
+        record.set("a.b.0", Difficulty::AT, chart(990_000, 100.0));
+        let mut constants = BTreeMap::new();
+        constants.insert("a.b.0".to_string(), vec![1.0, 2.0, 3.0, 15.0]);
+        assert!((compute(&record, &constants) - 15.0 / 30.0).abs() < 1e-6);
+    }
+}
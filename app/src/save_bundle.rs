@@ -0,0 +1,167 @@
+use crate::encryption::{decrypt_entry, encrypt_entry};
+use crate::error::PscError;
+use crate::game_key::field::GameKey;
+use crate::game_progress::field::GameProgress;
+use crate::game_record::field::GameRecord;
+use crate::payload::Payload;
+use crate::settings::field::Settings;
+use crate::user::field::User;
+use std::fmt;
+use std::io::{Cursor, Read, Write};
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
+
+pub const GAME_RECORD: &str = "gameRecord";
+pub const GAME_KEY: &str = "gameKey";
+pub const GAME_PROGRESS: &str = "gameProgress";
+pub const USER: &str = "user";
+pub const SETTINGS: &str = "settings";
+
+#[derive(Debug)]
+pub enum SaveBundleError {
+    Zip(ZipError),
+    Io(std::io::Error),
+    MissingEntry(&'static str),
+    Encryption {
+        entry: &'static str,
+        source: PscError,
+    },
+    Codec(PscError),
+}
+
+impl fmt::Display for SaveBundleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveBundleError::Zip(e) => write!(f, "压缩包错误: {}", e),
+            SaveBundleError::Io(e) => write!(f, "读写错误: {}", e),
+            SaveBundleError::MissingEntry(name) => write!(f, "缺少条目: {}", name),
+            SaveBundleError::Encryption { entry, source } => write!(f, "{}: {}", entry, source),
+            SaveBundleError::Codec(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SaveBundleError {}
+
+impl From<ZipError> for SaveBundleError {
+    fn from(e: ZipError) -> Self {
+        SaveBundleError::Zip(e)
+    }
+}
+
+impl From<PscError> for SaveBundleError {
+    fn from(e: PscError) -> Self {
+        SaveBundleError::Codec(e)
+    }
+}
+
+impl From<std::io::Error> for SaveBundleError {
+    fn from(e: std::io::Error) -> Self {
+        SaveBundleError::Io(e)
+    }
+}
+
+#[derive(Debug)]
+pub struct SaveBundle {
+    pub game_record: GameRecord,
+    pub game_key: GameKey,
+    pub game_progress: GameProgress,
+    pub user: User,
+    pub settings: Settings,
+    source: Vec<u8>,
+}
+
+impl SaveBundle {
+    pub fn parse(bytes: &[u8]) -> Result<Self, SaveBundleError> {
+        let mut archive = ZipArchive::new(Cursor::new(bytes))?;
+
+        Ok(SaveBundle {
+            game_record: parse_entry(&mut archive, GAME_RECORD)?,
+            game_key: parse_entry(&mut archive, GAME_KEY)?,
+            game_progress: parse_entry(&mut archive, GAME_PROGRESS)?,
+            user: parse_entry(&mut archive, USER)?,
+            settings: parse_entry(&mut archive, SETTINGS)?,
+            source: bytes.to_vec(),
+        })
+    }
+
+    // 未改动的条目原样拷贝,保证重建结果与原压缩包一致
+    pub fn build(&self) -> Result<Vec<u8>, SaveBundleError> {
+        let mut archive = ZipArchive::new(Cursor::new(self.source.as_slice()))?;
+        let mut writer = ZipWriter::new(Cursor::new(Vec::new()));
+        writer.set_raw_comment(archive.comment().into());
+
+        for i in 0..archive.len() {
+            let name = archive.by_index_raw(i)?.name().to_string();
+            let built = match name.as_str() {
+                GAME_RECORD => Some(build_entry(GAME_RECORD, &self.game_record)?),
+                GAME_KEY => Some(build_entry(GAME_KEY, &self.game_key)?),
+                GAME_PROGRESS => Some(build_entry(GAME_PROGRESS, &self.game_progress)?),
+                USER => Some(build_entry(USER, &self.user)?),
+                SETTINGS => Some(build_entry(SETTINGS, &self.settings)?),
+                _ => None,
+            };
+
+            let (entry, plain) = match built {
+                Some((entry, plain))
+                    if decrypt_entry(&read_raw(&mut archive, i)?).ok().as_ref() != Some(&plain) =>
+                {
+                    (entry, plain)
+                }
+                _ => {
+                    writer.raw_copy_file(archive.by_index_raw(i)?)?;
+                    continue;
+                }
+            };
+            let encrypted = encrypt_entry(&plain)
+                .map_err(|source| SaveBundleError::Encryption { entry, source })?;
+
+            let file = archive.by_index_raw(i)?;
+            let mut options = SimpleFileOptions::default().compression_method(file.compression());
+            if let Some(t) = file.last_modified() {
+                options = options.last_modified_time(t);
+            }
+            drop(file);
+
+            writer.start_file(name, options)?;
+            writer.write_all(&encrypted)?;
+        }
+
+        Ok(writer.finish()?.into_inner())
+    }
+}
+
+fn read_raw(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    index: usize,
+) -> Result<Vec<u8>, SaveBundleError> {
+    let mut file = archive.by_index(index)?;
+    let mut buf = Vec::with_capacity(file.size() as usize);
+    file.read_to_end(&mut buf)?;
+    Ok(buf)
+}
+
+fn parse_entry<T: Payload>(
+    archive: &mut ZipArchive<Cursor<&[u8]>>,
+    name: &'static str,
+) -> Result<T, SaveBundleError> {
+    let index = archive
+        .index_for_name(name)
+        .ok_or(SaveBundleError::MissingEntry(name))?;
+    let bytes = decrypt_entry(&read_raw(archive, index)?).map_err(|source| {
+        SaveBundleError::Encryption {
+            entry: name,
+            source,
+        }
+    })?;
+
+    Ok(T::parse_bytes(&bytes)?)
+}
+
+fn build_entry<T: Payload>(
+    name: &'static str,
+    item: &T,
+) -> Result<(&'static str, Vec<u8>), SaveBundleError> {
+    Ok((name, item.build_bytes()?))
+}
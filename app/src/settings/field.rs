@@ -23,4 +23,5 @@ pub struct Settings {
     pub hit_sound_volume: f32,
     pub sound_offset: f32,
     pub note_scale: f32,
+    pub extra: Extra,
 }
@@ -0,0 +1,56 @@
+use super::field::{Settings, SettingsBase};
+use crate::error::PscError;
+use crate::payload::{Payload, Walker};
+use crate::phi_base::Extra;
+
+impl Payload for Settings {
+    const NAME: &'static str = "settings";
+    const MAX_VERSION: Option<u8> = Some(1);
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn extra(&self) -> &Extra {
+        &self.extra
+    }
+
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError> {
+        w.read::<u8>("version")?;
+        w.read_aligned::<SettingsBase>("base", 8)?;
+        w.string("device_name")?;
+        for name in [
+            "bright",
+            "music_volume",
+            "effect_volume",
+            "hit_sound_volume",
+            "sound_offset",
+            "note_scale",
+        ] {
+            w.read::<f32>(name)?;
+        }
+        w.rest("extra");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::tests::assert_walk_matches_parse;
+    use crate::phi_base::PhiString;
+
+    #[test]
+    fn walk_matches_parse() {
+        let settings = Settings {
+            version: 1,
+            device_name: PhiString::from("设备"),
+            bright: 1.0,
+            music_volume: 0.8,
+            sound_offset: -0.05,
+            note_scale: 1.2,
+            ..Default::default()
+        };
+        assert_walk_matches_parse(&settings);
+    }
+}
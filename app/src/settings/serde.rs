@@ -21,6 +21,8 @@ pub struct SerializableSettings {
     pub hit_sound_volume: f32,
     pub sound_offset: f32,
     pub note_scale: f32,
+    #[serde(default, skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
 }
 
 impl From<SettingsBase> for SerializableSettingsBase {
@@ -57,6 +59,7 @@ impl From<Settings> for SerializableSettings {
             hit_sound_volume: s.hit_sound_volume,
             sound_offset: s.sound_offset,
             note_scale: s.note_scale,
+            extra: s.extra,
         }
     }
 }
@@ -73,6 +76,7 @@ impl From<SerializableSettings> for Settings {
             hit_sound_volume: s.hit_sound_volume,
             sound_offset: s.sound_offset,
             note_scale: s.note_scale,
+            extra: s.extra,
         }
     }
 }
@@ -0,0 +1,62 @@
+use super::field::Summary;
+use crate::error::PscError;
+use crate::payload::Payload;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD;
+
+// 云端存档对象的元数据中, summary 以 base64 字符串保存
+impl Summary {
+    pub fn from_base64(s: &str) -> Result<Self, PscError> {
+        let bytes = STANDARD
+            .decode(s.trim())
+            .map_err(|e| PscError::serialization(Self::NAME, e))?;
+        Self::parse_bytes(&bytes)
+    }
+
+    pub fn to_base64(&self) -> Result<String, PscError> {
+        Ok(STANDARD.encode(self.build_bytes()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+
+    // save_version 6, 课题模式 348, rks 15.5, 游戏版本 130, 头像 Introduction,
+    // IN phi 7 个, AT clear 120 个
+    const CLOUD_SUMMARY: &str = "BlwBAAB4QYIBDEludHJvZHVjdGlvbgAAAAAAAAAAAAAAAAAAAAAHAHgAAAAAAA==";
+
+    #[test]
+    fn known_summary_round_trips() {
+        let summary = Summary::from_base64(CLOUD_SUMMARY).unwrap();
+        assert_eq!(summary.save_version, 6);
+        assert_eq!(summary.challenge_mode_rank, 348);
+        assert_eq!(summary.rks, 15.5);
+        assert_eq!(summary.game_version.0, 130);
+        assert_eq!(summary.avatar.0, "Introduction");
+        assert_eq!(summary.level.r#in.phi, 7);
+        assert_eq!(summary.level.at.clear, 120);
+        assert!(summary.extra.is_empty());
+
+        assert_eq!(summary.to_base64().unwrap(), CLOUD_SUMMARY);
+        // 元数据中可能带换行等空白
+        assert!(Summary::from_base64(&format!(" {}\n", CLOUD_SUMMARY)).is_ok());
+    }
+
+    #[test]
+    fn invalid_base64_is_rejected() {
+        let err = Summary::from_base64("BlwB!AAB4").unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Serialization);
+        assert_eq!(err.path(), "summary");
+    }
+
+    #[test]
+    fn truncated_summary_is_rejected() {
+        let bytes = STANDARD.decode(CLOUD_SUMMARY).unwrap();
+        let truncated = STANDARD.encode(&bytes[..30]);
+        let err = Summary::from_base64(&truncated).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::Truncated);
+        assert!(err.path().starts_with("summary.level"), "{}", err.path());
+    }
+}
@@ -27,4 +27,5 @@ pub struct Summary {
     pub game_version: VarInt,
     pub avatar: PhiString,
     pub level: MultiLevel,
+    pub extra: Extra,
 }
@@ -1,4 +1,7 @@
+mod cloud;
 pub mod field;
+mod payload;
+mod recompute;
 
 #[cfg(feature = "serde")]
 pub mod serde;
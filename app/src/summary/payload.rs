@@ -0,0 +1,61 @@
+use super::field::Summary;
+use crate::error::PscError;
+use crate::payload::{Payload, Walker};
+use crate::phi_base::Extra;
+
+impl Payload for Summary {
+    const NAME: &'static str = "summary";
+    const VERSION_FIELD: &'static str = "save_version";
+    const MAX_VERSION: Option<u8> = None;
+
+    fn version(&self) -> u8 {
+        self.save_version
+    }
+
+    fn extra(&self) -> &Extra {
+        &self.extra
+    }
+
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError> {
+        w.read::<u8>("save_version")?;
+        w.read::<u16>("challenge_mode_rank")?;
+        w.read::<f32>("rks")?;
+        w.varint("game_version")?;
+        w.string("avatar")?;
+        w.nest("level", |w| {
+            for diff in ["ez", "hd", "in", "at"] {
+                w.nest(diff, |w| {
+                    w.read::<u16>("clear")?;
+                    w.read::<u16>("fc")?;
+                    w.read::<u16>("phi")?;
+                    Ok(())
+                })?;
+            }
+            Ok(())
+        })?;
+        w.rest("extra");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::payload::tests::assert_walk_matches_parse;
+    use crate::phi_base::{PhiString, VarInt};
+
+    #[test]
+    fn walk_matches_parse() {
+        let mut summary = Summary {
+            save_version: 6,
+            challenge_mode_rank: 348,
+            rks: 15.5,
+            game_version: VarInt(130),
+            avatar: PhiString::from("Introduction"),
+            ..Default::default()
+        };
+        summary.level.at.clear = 120;
+        summary.level.r#in.phi = 7;
+        assert_walk_matches_parse(&summary);
+    }
+}
@@ -0,0 +1,138 @@
+use super::field::{MultiLevel, Summary};
+use crate::diff::{Change, Diff};
+use crate::game_progress::field::GameProgress;
+use crate::game_record::field::GameRecord;
+use crate::game_record::song::Difficulty;
+use crate::grade::level_counts;
+use crate::rks::{ChartConstants, compute};
+
+impl Summary {
+    // 按存档内容重建统计字段,返回与原值不一致的字段
+    pub fn recompute<C: ChartConstants + ?Sized>(
+        &mut self,
+        record: &GameRecord,
+        progress: &GameProgress,
+        constants: &C,
+    ) -> Vec<Change> {
+        let fresh = Summary {
+            save_version: self.save_version,
+            challenge_mode_rank: progress.challenge_mode_rank,
+            rks: compute(record, constants),
+            game_version: self.game_version,
+            avatar: self.avatar.clone(),
+            level: MultiLevel {
+                ez: level_counts(record, Difficulty::EZ),
+                hd: level_counts(record, Difficulty::HD),
+                r#in: level_counts(record, Difficulty::IN),
+                at: level_counts(record, Difficulty::AT),
+            },
+            extra: self.extra.clone(),
+        };
+
+        let changes = self.diff(&fresh);
+        *self = fresh;
+        changes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::diff::Value;
+    use crate::game_record::song::ChartRecord;
+    use std::collections::BTreeMap;
+
+    fn chart(score: u32, acc: f32, fc: bool) -> ChartRecord {
+        ChartRecord { score, acc, fc }
+    }
+
+    fn record() -> GameRecord {
+        let mut record = GameRecord::default();
+        record.set("A.X.0", Difficulty::EZ, chart(1_000_000, 100.0, true));
+        record.set("A.X.0", Difficulty::IN, chart(950_000, 98.0, true));
+        record.set("B.Y.0", Difficulty::IN, chart(800_000, 90.0, false));
+        record.set("B.Y.0", Difficulty::AT, chart(600_000, 60.0, false));
+        record.set("B.Y.0", Difficulty::Legacy, chart(1_000_000, 100.0, true));
+        record
+    }
+
+    fn constants() -> BTreeMap<String, Vec<f32>> {
+        BTreeMap::from([
+            ("A.X.0".to_string(), vec![2.0, 5.0, 10.0]),
+            ("B.Y.0".to_string(), vec![1.0, 4.0, 12.0, 15.0, 9.0]),
+        ])
+    }
+
+    #[test]
+    fn rebuilds_level_counts_per_difficulty() {
+        let mut summary = Summary::default();
+        summary.recompute(&record(), &GameProgress::default(), &constants());
+
+        let level = |l: &crate::summary::field::Level| (l.clear, l.fc, l.phi);
+        assert_eq!(level(&summary.level.ez), (1, 1, 1));
+        assert_eq!(level(&summary.level.hd), (0, 0, 0));
+        assert_eq!(level(&summary.level.r#in), (2, 1, 0));
+        assert_eq!(level(&summary.level.at), (0, 0, 0));
+    }
+
+    #[test]
+    fn copies_rank_and_always_stores_computed_rks() {
+        let progress = GameProgress {
+            challenge_mode_rank: 348,
+            ..Default::default()
+        };
+        let expected = compute(&record(), &constants());
+        // 与计算值相差不足 0.005 时同样更新
+        let mut summary = Summary {
+            rks: expected + 0.001,
+            ..Default::default()
+        };
+        summary.recompute(&record(), &progress, &constants());
+
+        assert_eq!(summary.challenge_mode_rank, 348);
+        assert_eq!(summary.rks, expected);
+    }
+
+    #[test]
+    fn reports_disagreeing_fields() {
+        let progress = GameProgress {
+            challenge_mode_rank: 348,
+            ..Default::default()
+        };
+        let mut summary = Summary {
+            challenge_mode_rank: 348,
+            ..Default::default()
+        };
+        summary.level.r#in.clear = 2;
+        summary.level.at.clear = 1;
+
+        let changes = summary.recompute(&record(), &progress, &constants());
+        let field = |path: &str, old: i64, new: i64| Change::FieldChanged {
+            path: path.to_string(),
+            old: Value::Int(old),
+            new: Value::Int(new),
+        };
+        assert_eq!(
+            changes,
+            vec![
+                Change::FieldChanged {
+                    path: "summary.rks".to_string(),
+                    old: Value::Float(0.0),
+                    new: Value::Float(summary.rks),
+                },
+                field("summary.level.ez.clear", 0, 1),
+                field("summary.level.ez.fc", 0, 1),
+                field("summary.level.ez.phi", 0, 1),
+                field("summary.level.in.fc", 0, 1),
+                field("summary.level.at.clear", 1, 0),
+            ]
+        );
+
+        // 再次重建时不再有差异
+        assert!(
+            summary
+                .recompute(&record(), &progress, &constants())
+                .is_empty()
+        );
+    }
+}
@@ -1,4 +1,5 @@
 use super::field::{Level, MultiLevel, Summary};
+use crate::challenge::ChallengeRank;
 use crate::phi_base::*;
 use serde::{Deserialize, Serialize};
 
@@ -22,10 +23,15 @@ pub struct SerializableMultiLevel {
 pub struct SerializableSummary {
     pub save_version: u8,
     pub challenge_mode_rank: u16,
+    // 由 challenge_mode_rank 解码,仅用于输出,构建时以原始值为准
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub challenge_rank: Option<ChallengeRank>,
     pub rks: f32,
     pub game_version: u16,
     pub avatar: String,
     pub level: SerializableMultiLevel,
+    #[serde(default, skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
 }
 
 impl From<Level> for SerializableLevel {
@@ -75,10 +81,14 @@ impl From<Summary> for SerializableSummary {
         Self {
             save_version: s.save_version,
             challenge_mode_rank: s.challenge_mode_rank,
+            challenge_rank: ChallengeRank::from_raw(s.challenge_mode_rank)
+                .ok()
+                .flatten(),
             rks: s.rks,
             game_version: s.game_version.0,
             avatar: s.avatar.0,
             level: s.level.into(),
+            extra: s.extra,
         }
     }
 }
@@ -92,6 +102,7 @@ impl From<SerializableSummary> for Summary {
             game_version: VarInt(s.game_version),
             avatar: PhiString(s.avatar),
             level: s.level.into(),
+            extra: s.extra,
         }
     }
 }
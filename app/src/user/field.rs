@@ -10,4 +10,5 @@ pub struct User {
     pub self_intro: PhiString,
     pub avatar: PhiString,
     pub background: PhiString,
+    pub extra: Extra,
 }
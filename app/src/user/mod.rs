@@ -1,4 +1,5 @@
 pub mod field;
+mod payload;
 
 #[cfg(feature = "serde")]
 pub mod serde;
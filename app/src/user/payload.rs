@@ -0,0 +1,67 @@
+use super::field::User;
+use crate::error::PscError;
+use crate::payload::{Payload, Walker};
+use crate::phi_base::Extra;
+
+impl Payload for User {
+    const NAME: &'static str = "user";
+    const MAX_VERSION: Option<u8> = Some(1);
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+
+    fn extra(&self) -> &Extra {
+        &self.extra
+    }
+
+    fn walk(w: &mut Walker<'_>) -> Result<(), PscError> {
+        w.read::<u8>("version")?;
+        w.read_aligned::<bool>("show_player_id", 8)?;
+        w.string("self_intro")?;
+        w.string("avatar")?;
+        w.string("background")?;
+        w.rest("extra");
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorCode;
+    use crate::payload::ParseOptions;
+    use crate::payload::tests::assert_walk_matches_parse;
+    use crate::phi_base::PhiString;
+
+    fn user(self_intro: &str) -> User {
+        User {
+            version: 1,
+            show_player_id: true,
+            self_intro: PhiString::from(self_intro),
+            avatar: PhiString::from("Introduction"),
+            background: PhiString::from("Glaciaxion"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn walk_matches_parse() {
+        assert_walk_matches_parse(&user("你好"));
+    }
+
+    // 非法 UTF-8 只在严格模式下报错
+    #[test]
+    fn invalid_utf8_is_lossy_unless_strict() {
+        let mut bytes = user("ab").build_bytes().unwrap();
+        let at = bytes.iter().position(|b| *b == b'b').unwrap();
+        bytes[at] = 0xFF;
+
+        let parsed = User::parse_bytes(&bytes).unwrap();
+        assert_eq!(parsed.self_intro.0, "a?");
+        assert_eq!(parsed.avatar.0, "Introduction");
+
+        let err = User::parse_with(&bytes, &ParseOptions::strict()).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidString);
+    }
+}
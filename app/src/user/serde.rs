@@ -1,4 +1,5 @@
 use super::field::*;
+use crate::phi_base::Extra;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -8,6 +9,8 @@ pub struct SerializableUser {
     pub self_intro: String,
     pub avatar: String,
     pub background: String,
+    #[serde(default, skip_serializing_if = "Extra::is_empty")]
+    pub extra: Extra,
 }
 
 impl From<User> for SerializableUser {
@@ -18,6 +21,7 @@ impl From<User> for SerializableUser {
             self_intro: user.self_intro.into(),
             avatar: user.avatar.into(),
             background: user.background.into(),
+            extra: user.extra,
         }
     }
 }
@@ -30,6 +34,7 @@ impl From<SerializableUser> for User {
             self_intro: su.self_intro.into(),
             avatar: su.avatar.into(),
             background: su.background.into(),
+            extra: su.extra,
         }
     }
 }
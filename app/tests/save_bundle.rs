@@ -0,0 +1,58 @@
+#![cfg(feature = "save_bundle")]
+
+use phi_save_codec::game_record::song::{ChartRecord, Difficulty};
+use phi_save_codec::save_bundle::SaveBundle;
+
+const DEFLATED: &[u8] = include_bytes!("fixtures/deflated.save");
+const COMMENTED: &[u8] = include_bytes!("fixtures/commented.save");
+
+#[test]
+fn unmodified_bundle_rebuilds_byte_identical() {
+    for fixture in [DEFLATED, COMMENTED] {
+        let bundle = SaveBundle::parse(fixture).unwrap();
+        assert_eq!(bundle.build().unwrap(), fixture);
+    }
+}
+
+#[test]
+fn edited_entry_survives_rebuild() {
+    for fixture in [DEFLATED, COMMENTED] {
+        let mut bundle = SaveBundle::parse(fixture).unwrap();
+        let chart = ChartRecord {
+            score: 1000000,
+            acc: 100.0,
+            fc: true,
+        };
+        bundle
+            .game_record
+            .set("Glaciaxion.SunsetRay.0", Difficulty::IN, chart);
+
+        let rebuilt = SaveBundle::parse(&bundle.build().unwrap()).unwrap();
+        assert_eq!(
+            rebuilt
+                .game_record
+                .get("Glaciaxion.SunsetRay.0", Difficulty::IN),
+            Some(chart)
+        );
+        assert_eq!(rebuilt.user.self_intro.0, bundle.user.self_intro.0);
+        assert_eq!(
+            rebuilt.game_key.get("Introduction"),
+            bundle.game_key.get("Introduction")
+        );
+    }
+}
+
+#[test]
+fn unknown_entries_and_comment_are_kept() {
+    let mut bundle = SaveBundle::parse(COMMENTED).unwrap();
+    bundle.settings.device_name = "other-device".into();
+    let rebuilt = bundle.build().unwrap();
+
+    // 注释位于压缩包末尾;未知条目以 stored 方式存放,内容原样可见
+    assert!(rebuilt.ends_with(b"phigros cloud save"));
+    assert!(rebuilt.windows(13).any(|w| w == b"\x01opaque entry"));
+    assert_eq!(
+        SaveBundle::parse(&rebuilt).unwrap().settings.device_name.0,
+        "other-device"
+    );
+}
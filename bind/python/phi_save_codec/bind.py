@@ -1,9 +1,14 @@
+import struct
+
 import msgpack
 from wasmtime import Store, Module, Instance, Engine, Memory
 from phi_save_codec.error import PhiSaveCodecError
 
 
 class PhiSaveCodec:
+    # 与 WASM 模块中 psc_abi_version 的返回值一致
+    ABI_VERSION = 2
+
     def __init__(self, wasm: None | bytes = None):
         if wasm is None:
             from phi_save_codec.env import wasm as wasm
@@ -13,6 +18,39 @@ class PhiSaveCodec:
         self._instance = Instance(self._store, self._module, [])
         self._exports = self._instance.exports(self._store)
         self._mem: Memory = self._exports["memory"]  # pyright: ignore[reportAttributeAccessIssue]
+        self._check_abi()
+        # psc_data_free 的参数 Data 在 WASM 中按指针传递,预留一块内存存放
+        self._data_slot = self._malloc(8)
+
+    def _export(self, name: str):
+        try:
+            return self._exports[name]
+        except KeyError:
+            raise PhiSaveCodecError(f"当前 WASM 模块不支持 {name}")
+
+    def _check_abi(self) -> None:
+        abi_version = self._export("psc_abi_version")(self._store)  # pyright: ignore[reportCallIssue]
+        if abi_version != self.ABI_VERSION:
+            raise PhiSaveCodecError(
+                f"WASM 模块 ABI 版本为 {abi_version}, 绑定需要 {self.ABI_VERSION}"
+            )
+
+    def _read_data(self, data: tuple[int, int]) -> bytes:
+        size, ptr = data
+        if ptr == 0 or size == 0:
+            return b""
+        try:
+            return self._mem.read(self._store, ptr, ptr + size)
+        finally:
+            self._free(data)
+
+    def capabilities(self) -> dict:
+        out = self._export("psc_capabilities")(self._store)  # pyright: ignore[reportCallIssue]
+        return msgpack.unpackb(self._read_data(out), raw=False)
+
+    def crate_version(self) -> str:
+        out = self._export("psc_crate_version")(self._store)  # pyright: ignore[reportCallIssue]
+        return self._read_data(out).decode("utf-8")
 
     def _get_last_error(self) -> str:
         err_size, err_ptr = self._exports["psc_get_last_error"](self._store)  # pyright: ignore[reportCallIssue]
@@ -21,19 +59,24 @@ class PhiSaveCodec:
         try:
             error_bytes = self._mem.read(self._store, err_ptr, err_ptr + err_size)
             error_msg = error_bytes.decode("utf-8")
-            self._free(err_ptr, err_size)
+            self._free((err_size, err_ptr))
             return error_msg
         except Exception as e:
             return f"读取错误信息失败: {str(e)}"
 
+    def _get_last_error_code(self) -> int:
+        return self._exports["psc_get_last_error_code"](self._store)  # pyright: ignore[reportCallIssue]
+
     def _clear_last_error(self):
         if self._exports["psc_clear_last_error"](self._store) != 1:  # pyright: ignore[reportCallIssue]
             raise PhiSaveCodecError("没有错误")
 
-    def _free(self, ptr: int, size: int) -> None:
+    def _free(self, data: tuple[int, int]) -> None:
+        size, ptr = data
         if ptr == 0 or size == 0:
             return
-        result = self._exports["psc_free"](self._store, ptr, size)  # pyright: ignore[reportCallIssue]
+        self._mem.write(self._store, struct.pack("<II", size, ptr), self._data_slot)
+        result = self._exports["psc_data_free"](self._store, self._data_slot)  # pyright: ignore[reportCallIssue]
         if not result:
             raise PhiSaveCodecError("内存释放失败")
 
@@ -48,6 +91,8 @@ class PhiSaveCodec:
         return ptr
 
     def _invoke(self, func_name: str, in_data: bytes) -> bytes:
+        func = self._export("psc_" + func_name)
+
         # 写入数据
         in_size = len(in_data)
         in_ptr = self._malloc(in_size)
@@ -55,25 +100,27 @@ class PhiSaveCodec:
         try:
             self._mem.write(self._store, in_data, in_ptr)
             # 调用函数
-            out_size, out_ptr = self._exports["psc_" + func_name](
-                self._store, in_ptr, in_size
-            )  # pyright: ignore[reportCallIssue]
+            out = func(self._store, in_ptr, in_size)  # pyright: ignore[reportCallIssue]
+            out_size, out_ptr = out
 
             # 检查输出指针
             if out_ptr == 0:
+                error_code = self._get_last_error_code()
                 error_msg = self._get_last_error()
                 self._clear_last_error()
-                raise PhiSaveCodecError(f"函数调用失败 ({func_name}): {error_msg}")
+                raise PhiSaveCodecError(
+                    f"函数调用失败 ({func_name}): {error_msg}", error_code
+                )
 
             # 读取数据
             out_data = self._mem.read(self._store, out_ptr, out_ptr + out_size)
 
             # 释放临时内存
-            self._free(out_ptr, out_size)
+            self._free(out)
 
             return out_data
         finally:
-            self._free(in_ptr, in_size)
+            self._free((in_size, in_ptr))
 
     def _parse(self, name: str, data: bytes) -> dict:
         try:
@@ -89,6 +136,32 @@ class PhiSaveCodec:
         except msgpack.exceptions.PackException as e:
             raise PhiSaveCodecError(f"MessagePack 打包失败 ({name}): {str(e)}")
 
+    def _decrypt(self, name: str, data: bytes) -> dict:
+        try:
+            out = self._invoke(f"decrypt_{name}", data)
+            return msgpack.unpackb(out, raw=False)
+        except msgpack.exceptions.UnpackException as e:
+            raise PhiSaveCodecError(f"MessagePack 解包失败 ({name}): {str(e)}")
+
+    def _encrypt(self, name: str, obj: dict) -> bytes:
+        try:
+            packed_data: bytes = msgpack.packb(obj, use_bin_type=True)  # pyright: ignore[reportAssignmentType]
+            return self._invoke(f"encrypt_{name}", packed_data)
+        except msgpack.exceptions.PackException as e:
+            raise PhiSaveCodecError(f"MessagePack 打包失败 ({name}): {str(e)}")
+
+    def _diff(self, name: str, old: dict, new: dict) -> list:
+        try:
+            packed_data: bytes = msgpack.packb(
+                {"old": old, "new": new}, use_bin_type=True
+            )  # pyright: ignore[reportAssignmentType]
+            out = self._invoke(f"diff_{name}", packed_data)
+            return msgpack.unpackb(out, raw=False)
+        except msgpack.exceptions.PackException as e:
+            raise PhiSaveCodecError(f"MessagePack 打包失败 ({name}): {str(e)}")
+        except msgpack.exceptions.UnpackException as e:
+            raise PhiSaveCodecError(f"MessagePack 解包失败 ({name}): {str(e)}")
+
     def memory_size(self) -> int:
         return self._mem.data_len(self._store)
 
@@ -98,32 +171,98 @@ class PhiSaveCodec:
     def build_user(self, obj: dict) -> bytes:
         return self._build("user", obj)
 
+    def diff_user(self, old: dict, new: dict) -> list:
+        return self._diff("user", old, new)
+
+    def decrypt_user(self, data: bytes) -> dict:
+        return self._decrypt("user", data)
+
+    def encrypt_user(self, obj: dict) -> bytes:
+        return self._encrypt("user", obj)
+
     def parse_summary(self, data: bytes) -> dict:
         return self._parse("summary", data)
 
     def build_summary(self, obj: dict) -> bytes:
         return self._build("summary", obj)
 
+    def parse_summary_b64(self, data: str) -> dict:
+        return self._parse("summary_b64", data.encode())
+
+    def build_summary_b64(self, obj: dict) -> str:
+        return self._build("summary_b64", obj).decode()
+
+    def diff_summary(self, old: dict, new: dict) -> list:
+        return self._diff("summary", old, new)
+
     def parse_game_record(self, data: bytes) -> dict:
         return self._parse("game_record", data)
 
     def build_game_record(self, obj: dict) -> bytes:
         return self._build("game_record", obj)
 
+    def diff_game_record(self, old: dict, new: dict) -> list:
+        return self._diff("game_record", old, new)
+
+    def decrypt_game_record(self, data: bytes) -> dict:
+        return self._decrypt("game_record", data)
+
+    def encrypt_game_record(self, obj: dict) -> bytes:
+        return self._encrypt("game_record", obj)
+
     def parse_game_progress(self, data: bytes) -> dict:
         return self._parse("game_progress", data)
 
     def build_game_progress(self, obj: dict) -> bytes:
         return self._build("game_progress", obj)
 
+    def diff_game_progress(self, old: dict, new: dict) -> list:
+        return self._diff("game_progress", old, new)
+
+    def decrypt_game_progress(self, data: bytes) -> dict:
+        return self._decrypt("game_progress", data)
+
+    def encrypt_game_progress(self, obj: dict) -> bytes:
+        return self._encrypt("game_progress", obj)
+
     def parse_game_key(self, data: bytes) -> dict:
         return self._parse("game_key", data)
 
     def build_game_key(self, obj: dict) -> bytes:
         return self._build("game_key", obj)
 
+    def diff_game_key(self, old: dict, new: dict) -> list:
+        return self._diff("game_key", old, new)
+
+    def decrypt_game_key(self, data: bytes) -> dict:
+        return self._decrypt("game_key", data)
+
+    def encrypt_game_key(self, obj: dict) -> bytes:
+        return self._encrypt("game_key", obj)
+
     def parse_settings(self, data: bytes) -> dict:
         return self._parse("settings", data)
 
     def build_settings(self, obj: dict) -> bytes:
         return self._build("settings", obj)
+
+    def diff_settings(self, old: dict, new: dict) -> list:
+        return self._diff("settings", old, new)
+
+    def decrypt_settings(self, data: bytes) -> dict:
+        return self._decrypt("settings", data)
+
+    def encrypt_settings(self, obj: dict) -> bytes:
+        return self._encrypt("settings", obj)
+
+    def best_n(self, record: dict, constants: dict, n: int = 27) -> dict:
+        try:
+            packed_data: bytes = msgpack.packb(
+                {"record": record, "constants": constants, "n": n}, use_bin_type=True
+            )  # pyright: ignore[reportAssignmentType]
+            out = self._invoke("best_n", packed_data)
+            return msgpack.unpackb(out, raw=False)
+        except msgpack.exceptions.PackException as e:
+            raise PhiSaveCodecError(f"MessagePack 打包失败 (best_n): {str(e)}")
+        except msgpack.exceptions.UnpackException as e:
+            raise PhiSaveCodecError(f"MessagePack 解包失败 (best_n): {str(e)}")
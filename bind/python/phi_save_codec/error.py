@@ -1,2 +1,4 @@
 class PhiSaveCodecError(Exception):
-    pass
+    def __init__(self, message: str, code: int = 0):
+        super().__init__(message)
+        self.code = code
@@ -0,0 +1,353 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use phi_save_codec::chart_table::ChartTable;
+use phi_save_codec::diff::Diff;
+use phi_save_codec::encryption::{self, decrypt_entry};
+use phi_save_codec::game_key::{field::GameKey, serde::SerializableGameKey};
+use phi_save_codec::game_progress::{field::GameProgress, serde::SerializableGameProgress};
+use phi_save_codec::game_record::{field::GameRecord, serde::SerializableGameRecord};
+use phi_save_codec::layout::layout;
+use phi_save_codec::payload::{ParseOptions, Payload};
+use phi_save_codec::settings::{field::Settings, serde::SerializableSettings};
+use phi_save_codec::summary::{field::Summary, serde::SerializableSummary};
+use phi_save_codec::user::{field::User, serde::SerializableUser};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::error::Error;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+
+type Result<T> = std::result::Result<T, Box<dyn Error>>;
+
+#[derive(Parser)]
+#[command(name = "psc", version, about = "Phigros 存档编解码工具")]
+struct Cli {
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    #[command(about = "二进制 -> JSON/MessagePack/YAML")]
+    Decode {
+        r#type: PayloadType,
+        file: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        #[arg(help = "输入为存档 zip 中加密的条目", short, long)]
+        encrypted: bool,
+        #[arg(help = "输出评级与完成状态等推导字段", short, long)]
+        derived: bool,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    #[command(
+        about = "JSON/MessagePack/YAML -> 二进制,不指定文件时从标准输入读取",
+        after_help = "game_key 的 key 在解码结果中按名字排序,编码后顺序可能与原存档不同,不保证逐字节一致"
+    )]
+    Encode {
+        r#type: PayloadType,
+        file: Option<PathBuf>,
+        #[arg(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        #[arg(help = "输出加密后的条目", short, long)]
+        encrypted: bool,
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    #[command(about = "按字段标注的十六进制转储")]
+    Inspect {
+        r#type: PayloadType,
+        file: PathBuf,
+        #[arg(help = "输出字段树的 JSON", short, long)]
+        json: bool,
+        #[arg(short, long)]
+        encrypted: bool,
+    },
+    #[command(about = "比较两份同类型的存档")]
+    Diff {
+        r#type: PayloadType,
+        old: PathBuf,
+        new: PathBuf,
+        #[arg(short, long, value_enum, default_value_t = Format::Json)]
+        format: Format,
+        #[arg(short, long)]
+        encrypted: bool,
+    },
+    #[command(about = "检查存档能否解析,可选严格模式与定数表校验")]
+    Validate {
+        r#type: PayloadType,
+        file: PathBuf,
+        #[arg(short, long)]
+        encrypted: bool,
+        #[arg(short, long)]
+        strict: bool,
+        #[arg(help = "定数表 (CSV/TSV/JSON),仅用于 game_record", short, long)]
+        charts: Option<PathBuf>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+#[value(rename_all = "snake_case")]
+enum PayloadType {
+    User,
+    Summary,
+    GameRecord,
+    GameProgress,
+    GameKey,
+    Settings,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Json,
+    Msgpack,
+    Yaml,
+}
+
+trait Codec: Payload + Diff + From<Self::Serializable> {
+    type Serializable: Serialize + DeserializeOwned + From<Self>;
+
+    // 附加评级等推导字段,目前只有 game_record 有
+    fn derived(s: Self::Serializable) -> Self::Serializable {
+        s
+    }
+}
+
+macro_rules! impl_codec {
+    ($struct_ty:ty, $serializable_ty:ty) => {
+        impl Codec for $struct_ty {
+            type Serializable = $serializable_ty;
+        }
+    };
+}
+
+impl_codec!(User, SerializableUser);
+impl_codec!(Summary, SerializableSummary);
+impl Codec for GameRecord {
+    type Serializable = SerializableGameRecord;
+
+    fn derived(s: Self::Serializable) -> Self::Serializable {
+        s.with_grades()
+    }
+}
+impl_codec!(GameProgress, SerializableGameProgress);
+impl_codec!(GameKey, SerializableGameKey);
+impl_codec!(Settings, SerializableSettings);
+
+macro_rules! dispatch {
+    ($ty:expr, $func:ident($($arg:expr),*)) => {
+        match $ty {
+            PayloadType::User => $func::<User>($($arg),*),
+            PayloadType::Summary => $func::<Summary>($($arg),*),
+            PayloadType::GameRecord => $func::<GameRecord>($($arg),*),
+            PayloadType::GameProgress => $func::<GameProgress>($($arg),*),
+            PayloadType::GameKey => $func::<GameKey>($($arg),*),
+            PayloadType::Settings => $func::<Settings>($($arg),*),
+        }
+    };
+}
+
+fn read_input(path: Option<&Path>) -> Result<Vec<u8>> {
+    match path {
+        Some(p) if p != Path::new("-") => Ok(fs::read(p)?),
+        _ => {
+            let mut buf = Vec::new();
+            io::stdin().read_to_end(&mut buf)?;
+            Ok(buf)
+        }
+    }
+}
+
+fn write_output(path: Option<&Path>, bytes: &[u8]) -> Result<()> {
+    match path {
+        Some(p) => fs::write(p, bytes)?,
+        None => io::stdout().write_all(bytes)?,
+    }
+    Ok(())
+}
+
+fn plain(bytes: Vec<u8>, encrypted: bool) -> Result<Vec<u8>> {
+    if encrypted {
+        Ok(decrypt_entry(&bytes)?)
+    } else {
+        Ok(bytes)
+    }
+}
+
+fn load<T: Codec>(path: &Path, encrypted: bool, options: &ParseOptions) -> Result<T> {
+    let bytes = plain(fs::read(path)?, encrypted)?;
+    Ok(T::parse_with(&bytes, options)?)
+}
+
+fn serialize<S: Serialize>(value: &S, format: Format) -> Result<Vec<u8>> {
+    Ok(match format {
+        Format::Json => {
+            let mut v = serde_json::to_vec_pretty(value)?;
+            v.push(b'\n');
+            v
+        }
+        Format::Msgpack => rmp_serde::to_vec_named(value)?,
+        Format::Yaml => serde_yaml::to_string(value)?.into_bytes(),
+    })
+}
+
+fn deserialize<S: DeserializeOwned>(bytes: &[u8], format: Format) -> Result<S> {
+    Ok(match format {
+        Format::Json => serde_json::from_slice(bytes)?,
+        Format::Msgpack => rmp_serde::from_slice(bytes)?,
+        Format::Yaml => serde_yaml::from_slice(bytes)?,
+    })
+}
+
+fn decode<T: Codec>(
+    file: &Path,
+    format: Format,
+    encrypted: bool,
+    derived: bool,
+    output: Option<&Path>,
+) -> Result<()> {
+    let item: T = load(file, encrypted, &ParseOptions::default())?;
+    let mut serializable = T::Serializable::from(item);
+    if derived {
+        serializable = T::derived(serializable);
+    }
+    let out = serialize(&serializable, format)?;
+    write_output(output, &out)
+}
+
+fn encode<T: Codec>(
+    file: Option<&Path>,
+    format: Format,
+    encrypted: bool,
+    output: Option<&Path>,
+) -> Result<()> {
+    let serializable: T::Serializable = deserialize(&read_input(file)?, format)?;
+    let item = T::from(serializable);
+    let out = if encrypted {
+        encryption::build_encrypted(&item)?
+    } else {
+        item.build_bytes()?
+    };
+    write_output(output, &out)
+}
+
+fn inspect<T: Codec>(file: &Path, json: bool, encrypted: bool) -> Result<()> {
+    let bytes = plain(fs::read(file)?, encrypted)?;
+    let layout = layout::<T>(&bytes);
+    let text = if json {
+        layout.to_json() + "\n"
+    } else {
+        layout.root.to_text()
+    };
+    write_output(None, text.as_bytes())?;
+    match layout.error {
+        Some(e) => Err(e.into()),
+        None => Ok(()),
+    }
+}
+
+fn diff<T: Codec>(old: &Path, new: &Path, format: Format, encrypted: bool) -> Result<()> {
+    let options = ParseOptions::default();
+    let old: T = load(old, encrypted, &options)?;
+    let new: T = load(new, encrypted, &options)?;
+    write_output(None, &serialize(&old.diff(&new), format)?)
+}
+
+fn validate<T: Codec>(file: &Path, encrypted: bool, strict: bool) -> Result<T> {
+    let options = if strict {
+        ParseOptions::strict()
+    } else {
+        ParseOptions::default()
+    };
+    let item: T = load(file, encrypted, &options)?;
+    if !item.extra().is_empty() {
+        println!("注意: 末尾有 {} 字节未识别的数据", item.extra().0.len());
+    }
+    Ok(item)
+}
+
+fn check<T: Codec>(file: &Path, encrypted: bool, strict: bool) -> Result<()> {
+    validate::<T>(file, encrypted, strict).map(|_| ())
+}
+
+fn load_charts(path: &Path) -> Result<ChartTable> {
+    let text = fs::read_to_string(path)?;
+    let table = if path.extension().is_some_and(|e| e == "json") {
+        ChartTable::from_json(&text)?
+    } else {
+        ChartTable::from_csv(&text)?
+    };
+    Ok(table)
+}
+
+fn run(cli: Cli) -> Result<()> {
+    match cli.command {
+        Command::Decode {
+            r#type,
+            file,
+            format,
+            encrypted,
+            derived,
+            output,
+        } => dispatch!(
+            r#type,
+            decode(&file, format, encrypted, derived, output.as_deref())
+        ),
+        Command::Encode {
+            r#type,
+            file,
+            format,
+            encrypted,
+            output,
+        } => dispatch!(
+            r#type,
+            encode(file.as_deref(), format, encrypted, output.as_deref())
+        ),
+        Command::Inspect {
+            r#type,
+            file,
+            json,
+            encrypted,
+        } => dispatch!(r#type, inspect(&file, json, encrypted)),
+        Command::Diff {
+            r#type,
+            old,
+            new,
+            format,
+            encrypted,
+        } => dispatch!(r#type, diff(&old, &new, format, encrypted)),
+        Command::Validate {
+            r#type,
+            file,
+            encrypted,
+            strict,
+            charts,
+        } => {
+            match (r#type, charts) {
+                (PayloadType::GameRecord, Some(charts)) => {
+                    let record: GameRecord = validate(&file, encrypted, strict)?;
+                    let warnings = load_charts(&charts)?.validate(&record);
+                    for w in &warnings {
+                        println!("警告: {}", serde_json::to_string(w)?);
+                    }
+                }
+                (_, Some(_)) => return Err("定数表只能用于 game_record".into()),
+                (ty, None) => dispatch!(ty, check(&file, encrypted, strict))?,
+            }
+            println!("OK");
+            Ok(())
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    match run(Cli::parse()) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("错误: {}", e);
+            ExitCode::FAILURE
+        }
+    }
+}
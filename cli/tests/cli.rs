@@ -0,0 +1,238 @@
+use phi_save_codec::encryption;
+use phi_save_codec::game_key::entry::KeyEntry;
+use phi_save_codec::game_key::field::GameKey;
+use phi_save_codec::game_progress::field::GameProgress;
+use phi_save_codec::game_record::field::GameRecord;
+use phi_save_codec::game_record::song::{ChartRecord, Difficulty};
+use phi_save_codec::payload::Payload;
+use phi_save_codec::settings::field::Settings;
+use phi_save_codec::summary::field::Summary;
+use phi_save_codec::user::field::User;
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn psc(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_psc"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+// 每个测试使用独立的目录,避免并行运行时互相覆盖
+fn write(test: &str, name: &str, bytes: &[u8]) -> String {
+    let dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(test);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, bytes).unwrap();
+    path.to_str().unwrap().to_string()
+}
+
+fn record() -> GameRecord {
+    let mut record = GameRecord {
+        version: 1,
+        ..Default::default()
+    };
+    let chart = ChartRecord {
+        score: 995_000,
+        acc: 99.5,
+        fc: true,
+    };
+    record.set("Glaciaxion.SunsetRay.0", Difficulty::IN, chart);
+    record.set("Glaciaxion.SunsetRay.0", Difficulty::AT, chart);
+    record
+}
+
+fn samples() -> Vec<(&'static str, Vec<u8>)> {
+    let mut key = GameKey {
+        version: 3,
+        camellia_read_key: Some(true),
+        side_story4_begin_read_key: Some(false),
+        old_score_cleared_v390: Some(true),
+        ..Default::default()
+    };
+    key.set("Glaciaxion", KeyEntry::song(true));
+
+    vec![
+        (
+            "user",
+            User {
+                version: 1,
+                self_intro: "你好".into(),
+                avatar: "Glaciaxion".into(),
+                ..Default::default()
+            }
+            .build_bytes()
+            .unwrap(),
+        ),
+        (
+            "summary",
+            Summary {
+                save_version: 6,
+                challenge_mode_rank: 348,
+                rks: 12.5,
+                avatar: "Glaciaxion".into(),
+                ..Default::default()
+            }
+            .build_bytes()
+            .unwrap(),
+        ),
+        ("game_record", record().build_bytes().unwrap()),
+        (
+            "game_progress",
+            GameProgress {
+                version: 4,
+                completed: "3.0".into(),
+                flag_of_song_record_key_takumi: Some([true, false, true]),
+                ..Default::default()
+            }
+            .build_bytes()
+            .unwrap(),
+        ),
+        ("game_key", key.build_bytes().unwrap()),
+        (
+            "settings",
+            Settings {
+                version: 1,
+                device_name: "phone".into(),
+                music_volume: 0.75,
+                ..Default::default()
+            }
+            .build_bytes()
+            .unwrap(),
+        ),
+    ]
+}
+
+#[test]
+fn decode_then_encode_round_trips() {
+    for (ty, bytes) in samples() {
+        let input = write("round_trip", &format!("{}.bin", ty), &bytes);
+        for format in ["json", "msgpack", "yaml"] {
+            let decoded = write("round_trip", &format!("{}.{}", ty, format), &[]);
+            let encoded = write("round_trip", &format!("{}.{}.bin", ty, format), &[]);
+
+            let out = psc(&["decode", ty, &input, "-f", format, "-o", &decoded]);
+            assert!(out.status.success(), "{} {}: {:?}", ty, format, out);
+            let out = psc(&["encode", ty, &decoded, "-f", format, "-o", &encoded]);
+            assert!(out.status.success(), "{} {}: {:?}", ty, format, out);
+
+            assert_eq!(fs::read(&encoded).unwrap(), bytes, "{} {}", ty, format);
+        }
+    }
+}
+
+#[test]
+fn encrypted_round_trip() {
+    let bytes = encryption::build_encrypted(&record()).unwrap();
+    let input = write("encrypted", "gameRecord", &bytes);
+    let decoded = write("encrypted", "record.json", &[]);
+    let encoded = write("encrypted", "gameRecord.out", &[]);
+
+    assert!(
+        psc(&["decode", "game_record", &input, "-e", "-o", &decoded])
+            .status
+            .success()
+    );
+    assert!(
+        psc(&["encode", "game_record", &decoded, "-e", "-o", &encoded])
+            .status
+            .success()
+    );
+    assert_eq!(fs::read(&encoded).unwrap(), bytes);
+}
+
+// key 在 JSON 中按名字排序,重新编码后顺序改变,但内容不变
+#[test]
+fn game_key_encode_reorders_keys() {
+    let mut key = GameKey::default();
+    key.set("Introduction", KeyEntry::collection(2, 3));
+    key.set("Glaciaxion", KeyEntry::song(true));
+    let bytes = key.build_bytes().unwrap();
+    let input = write("game_key_order", "gameKey", &bytes);
+    let decoded = write("game_key_order", "key.json", &[]);
+    let encoded = write("game_key_order", "gameKey.out", &[]);
+
+    assert!(
+        psc(&["decode", "game_key", &input, "-o", &decoded])
+            .status
+            .success()
+    );
+    assert!(
+        psc(&["encode", "game_key", &decoded, "-o", &encoded])
+            .status
+            .success()
+    );
+
+    let rebuilt = fs::read(&encoded).unwrap();
+    assert_ne!(rebuilt, bytes);
+    assert_eq!(rebuilt.len(), bytes.len());
+    let rebuilt = GameKey::parse_bytes(&rebuilt).unwrap();
+    let names: Vec<&str> = rebuilt.iter_entries().map(|(name, _)| name).collect();
+    assert_eq!(names, ["Glaciaxion", "Introduction"]);
+    assert_eq!(rebuilt.get("Introduction"), key.get("Introduction"));
+}
+
+#[test]
+fn validate_exit_codes() {
+    let bytes = record().build_bytes().unwrap();
+    let valid = write("validate", "valid", &bytes);
+    let truncated = write("validate", "truncated", &bytes[..bytes.len() - 4]);
+    let mut trailing = bytes.clone();
+    trailing.push(0);
+    let trailing = write("validate", "trailing", &trailing);
+    let charts = write(
+        "validate",
+        "charts.csv",
+        b"Glaciaxion.SunsetRay.0,1,2,3,4\n",
+    );
+
+    let out = psc(&["validate", "game_record", &valid, "--strict"]);
+    assert!(out.status.success(), "{:?}", out);
+    assert!(String::from_utf8_lossy(&out.stdout).contains("OK"));
+
+    let out = psc(&["validate", "game_record", &truncated]);
+    assert_eq!(out.status.code(), Some(1));
+    assert!(String::from_utf8_lossy(&out.stderr).starts_with("错误: "));
+
+    // 多余数据只在严格模式下报错
+    assert!(
+        psc(&["validate", "game_record", &trailing])
+            .status
+            .success()
+    );
+    let out = psc(&["validate", "game_record", &trailing, "--strict"]);
+    assert_eq!(out.status.code(), Some(1));
+
+    assert!(
+        psc(&["validate", "game_record", &valid, "-c", &charts])
+            .status
+            .success()
+    );
+    let out = psc(&["validate", "user", &valid, "-c", &charts]);
+    assert_eq!(out.status.code(), Some(1));
+}
+
+#[test]
+fn diff_exit_codes() {
+    let old = record();
+    let mut new = record();
+    new.remove("Glaciaxion.SunsetRay.0", Difficulty::AT);
+    let old = write("diff", "old", &old.build_bytes().unwrap());
+    let new = write("diff", "new", &new.build_bytes().unwrap());
+
+    let out = psc(&["diff", "game_record", &old, &old]);
+    assert!(out.status.success(), "{:?}", out);
+    let changes: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(changes, serde_json::json!([]));
+
+    // 有差异时同样返回 0,差异写到标准输出
+    let out = psc(&["diff", "game_record", &old, &new]);
+    assert!(out.status.success(), "{:?}", out);
+    let changes: serde_json::Value = serde_json::from_slice(&out.stdout).unwrap();
+    assert_eq!(changes.as_array().unwrap().len(), 1);
+
+    let missing = format!("{}.missing", old);
+    let out = psc(&["diff", "game_record", &old, &missing]);
+    assert_eq!(out.status.code(), Some(1));
+}
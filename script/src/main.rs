@@ -1,10 +1,28 @@
 use multi_value_gen::parse;
 use std::collections::HashMap;
 use std::fs;
+use std::path::Path;
 use std::process::Command;
 use syn::parse_file;
 use walrus::ValType;
 
+#[path = "../../app/build/header.rs"]
+mod header;
+
+use header::macro_export_names;
+
+const APP_DIR: &str = "./app";
+
+fn returns_data(sig: &syn::Signature) -> bool {
+    match &sig.output {
+        syn::ReturnType::Type(_, ty) => match ty.as_ref() {
+            syn::Type::Path(p) => p.path.is_ident("Data"),
+            _ => false,
+        },
+        syn::ReturnType::Default => false,
+    }
+}
+
 fn extract_functions_from_c_api(
     c_api_path: &str,
 ) -> Result<HashMap<String, Vec<ValType>>, Box<dyn std::error::Error>> {
@@ -17,24 +35,22 @@ fn extract_functions_from_c_api(
     ); // 固定的
 
     for item in file.items {
-        if let syn::Item::Macro(item_macro) = item
-            && item_macro.mac.path.is_ident("impl_c_api")
-        {
-            let tokens = &item_macro.mac.tokens;
-            let tokens_str = tokens.to_string();
-
-            let params_str = tokens_str.trim_start_matches('(').trim_end_matches(')');
-            let params: Vec<&str> = params_str.split(',').map(|s: &str| s.trim()).collect();
-
-            if params.len() >= 4 {
-                let parse_fn = params[2];
-                let build_fn = params[3];
-
-                funcs.insert(parse_fn.to_string(), vec![ValType::I32, ValType::I32]);
-                funcs.insert(build_fn.to_string(), vec![ValType::I32, ValType::I32]);
-
-                println!("提取函数: {} {}", parse_fn, build_fn);
+        match item {
+            syn::Item::Macro(item_macro) => {
+                for name in macro_export_names(&item_macro.mac).unwrap_or_default() {
+                    println!("提取函数: {}", name);
+                    funcs.insert(name, vec![ValType::I32, ValType::I32]);
+                }
             }
+            // 手写的导出函数,返回 Data 的需要多返回值处理
+            syn::Item::Fn(item_fn) if returns_data(&item_fn.sig) => {
+                let name = item_fn.sig.ident.to_string();
+                if name.starts_with("psc_") {
+                    println!("提取函数: {}", name);
+                    funcs.insert(name, vec![ValType::I32, ValType::I32]);
+                }
+            }
+            _ => {}
         }
     }
 
@@ -42,6 +58,28 @@ fn extract_functions_from_c_api(
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+
+    // header: 只生成 C 头文件与 pkg-config 文件, --check 时检查已提交的文件是否过期
+    if args.first().map(String::as_str) == Some("header") {
+        let check = args.iter().any(|a| a == "--check");
+        let stale = header::write_or_check(Path::new(APP_DIR), check)?;
+        for path in &stale {
+            if check {
+                eprintln!(
+                    "{} 与 c_api.rs 不一致,请运行 cargo run -p script -- header",
+                    path.display()
+                );
+            } else {
+                println!("保存到: {}", path.display());
+            }
+        }
+        if check && !stale.is_empty() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     let status = Command::new("cargo")
         .args([
             "build",
@@ -49,7 +87,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             "-p",
             "phi_save_codec",
             "--features",
-            "c_abi",
+            "c_abi,encryption",
             "--release",
             "--target",
             "wasm32-unknown-unknown",
@@ -100,3 +138,20 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::header;
+    use std::fs;
+    use std::path::Path;
+
+    // 头文件与 pkg-config 文件需与 c_api.rs 保持一致,过期时运行 cargo run -p script -- header
+    #[test]
+    fn generated_files_are_up_to_date() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("../app");
+        let header = fs::read_to_string(dir.join(header::HEADER_PATH)).unwrap();
+        assert_eq!(header, header::generate_header(&dir).unwrap());
+        let pc = fs::read_to_string(dir.join(header::PKG_CONFIG_PATH)).unwrap();
+        assert_eq!(pc, header::generate_pkg_config(&dir).unwrap());
+    }
+}